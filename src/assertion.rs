@@ -0,0 +1,315 @@
+use std::fmt;
+
+use regex::Regex;
+use serde_json::Value;
+use yaml_rust::Yaml;
+
+use crate::interpolator::hrw_value_to_string;
+use crate::schema::HarrawSchema;
+use crate::service::hrw_yaml_to_json;
+
+static TYPES: [&str; 7] = ["string", "number", "integer", "boolean", "array", "object", "null"];
+
+#[derive(Clone, Debug)]
+pub enum HarrawOperator {
+  Equals(Value),
+  NotEquals(Value),
+  Lt(f64),
+  Gt(f64),
+  Lte(f64),
+  Gte(f64),
+  Contains(Value),
+  Matches(Regex),
+  In(Vec<Value>),
+  Exists(bool),
+  Length(usize),
+  Type(String),
+  Schema(HarrawSchema),
+}
+
+/// A list of operators that all need to hold for the resolved value.
+#[derive(Clone, Debug)]
+pub struct HarrawAssertion {
+  operators: Vec<HarrawOperator>,
+}
+
+impl HarrawAssertion {
+  /// Reads `operator: expected` pairs out of a hash, ignoring the `reserved` keys.
+  /// Any other node is compared with `equals`. Schema paths are relative to `parent_path`.
+  pub fn new(parent_path: &str, node: &Yaml, reserved: &[&str]) -> HarrawAssertion {
+    let mut operators = Vec::new();
+
+    if let Some(hash) = node.as_hash() {
+      for (key, expected) in hash.iter() {
+        let key = key.as_str().expect("Assertion operators must be strings");
+
+        if reserved.contains(&key) {
+          continue;
+        }
+        operators.push(HarrawOperator::new(parent_path, key, expected));
+      }
+    } else {
+      operators.push(HarrawOperator::Equals(hrw_yaml_to_json(node.clone())));
+    }
+
+    if operators.is_empty() {
+      panic!("Assertion needs at least one operator");
+    }
+
+    HarrawAssertion { operators }
+  }
+
+  pub fn hrw_from_operator(parent_path: &str, name: &str, expected: &Yaml) -> HarrawAssertion {
+    HarrawAssertion {
+      operators: vec![HarrawOperator::new(parent_path, name, expected)],
+    }
+  }
+
+  pub fn hrw_evaluate(&self, actual: Option<&Value>) -> Result<(), String> {
+    for operator in self.operators.iter() {
+      operator.hrw_evaluate(actual)?;
+    }
+    Ok(())
+  }
+}
+
+impl HarrawOperator {
+  pub fn new(parent_path: &str, name: &str, expected: &Yaml) -> HarrawOperator {
+    match name {
+      "equals" | "value" => HarrawOperator::Equals(hrw_yaml_to_json(expected.clone())),
+      "not_equals" => HarrawOperator::NotEquals(hrw_yaml_to_json(expected.clone())),
+      "lt" => HarrawOperator::Lt(hrw_expected_number(name, expected)),
+      "gt" => HarrawOperator::Gt(hrw_expected_number(name, expected)),
+      "lte" => HarrawOperator::Lte(hrw_expected_number(name, expected)),
+      "gte" => HarrawOperator::Gte(hrw_expected_number(name, expected)),
+      "contains" => HarrawOperator::Contains(hrw_yaml_to_json(expected.clone())),
+      "matches" => {
+        let pattern = expected.as_str().expect("`matches` needs to be a string");
+        HarrawOperator::Matches(Regex::new(pattern).unwrap_or_else(|e| panic!("Invalid `matches` regex: {e}")))
+      }
+      "in" => match hrw_yaml_to_json(expected.clone()) {
+        Value::Array(values) => HarrawOperator::In(values),
+        _ => panic!("`in` needs to be a list"),
+      },
+      "exists" => HarrawOperator::Exists(expected.as_bool().expect("`exists` needs to be a boolean")),
+      "not_exists" => HarrawOperator::Exists(!expected.as_bool().expect("`not_exists` needs to be a boolean")),
+      "length" => {
+        let length = expected.as_i64().expect("`length` needs to be a number");
+        HarrawOperator::Length(usize::try_from(length).expect("`length` should not be negative"))
+      }
+      "type" => {
+        let kind = expected.as_str().expect("`type` needs to be a string");
+        let kind = match kind {
+          "bool" => "boolean",
+          "list" => "array",
+          "map" | "hash" => "object",
+          other => other,
+        };
+
+        if !TYPES.contains(&kind) {
+          panic!("Unknown type `{}`, expected one of {:?}", kind, TYPES);
+        }
+        HarrawOperator::Type(kind.to_string())
+      }
+      "schema" => HarrawOperator::Schema(HarrawSchema::new(parent_path, expected.as_str().expect("`schema` needs to be a file path"))),
+      _ => panic!("Unknown assertion operator `{}`", name),
+    }
+  }
+
+  pub fn hrw_evaluate(&self, actual: Option<&Value>) -> Result<(), String> {
+    let value = match (self, actual) {
+      (HarrawOperator::Exists(true), None) => return Err("is missing".to_string()),
+      (HarrawOperator::Exists(false), Some(value)) => return Err(format!("exists with value `{}`", hrw_value_to_string(value))),
+      (HarrawOperator::Exists(_), _) => return Ok(()),
+      (_, None) => return Err("is missing".to_string()),
+      (_, Some(value)) => value,
+    };
+    let rendered = hrw_value_to_string(value);
+
+    let holds = match self {
+      HarrawOperator::Equals(expected) => hrw_loose_eq(value, expected),
+      HarrawOperator::NotEquals(expected) => !hrw_loose_eq(value, expected),
+      HarrawOperator::Lt(expected) => hrw_actual_number(value)? < *expected,
+      HarrawOperator::Gt(expected) => hrw_actual_number(value)? > *expected,
+      HarrawOperator::Lte(expected) => hrw_actual_number(value)? <= *expected,
+      HarrawOperator::Gte(expected) => hrw_actual_number(value)? >= *expected,
+      HarrawOperator::Contains(expected) => match value {
+        Value::Array(items) => items.iter().any(|item| hrw_loose_eq(item, expected)),
+        Value::Object(map) => map.contains_key(&hrw_value_to_string(expected)),
+        _ => rendered.contains(&hrw_value_to_string(expected)),
+      },
+      HarrawOperator::Matches(regex) => regex.is_match(&rendered),
+      HarrawOperator::In(expected) => expected.iter().any(|item| hrw_loose_eq(value, item)),
+      HarrawOperator::Length(expected) => {
+        let length = match value {
+          Value::String(s) => s.chars().count(),
+          Value::Array(items) => items.len(),
+          Value::Object(map) => map.len(),
+          _ => return Err(format!("`{rendered}` has no length")),
+        };
+        length == *expected
+      }
+      HarrawOperator::Type(expected) => hrw_type_of(value) == expected || (expected == "number" && value.is_number()),
+      HarrawOperator::Schema(schema) => return schema.hrw_validate(value).map_err(|violations| format!("does not match schema {schema}: {violations}")),
+      HarrawOperator::Exists(_) => unreachable!(),
+    };
+
+    if holds {
+      Ok(())
+    } else {
+      Err(format!("`{rendered}` is not {self}"))
+    }
+  }
+}
+
+impl fmt::Display for HarrawOperator {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      HarrawOperator::Equals(v) => write!(f, "equal to `{}`", hrw_value_to_string(v)),
+      HarrawOperator::NotEquals(v) => write!(f, "different from `{}`", hrw_value_to_string(v)),
+      HarrawOperator::Lt(v) => write!(f, "lower than {v}"),
+      HarrawOperator::Gt(v) => write!(f, "greater than {v}"),
+      HarrawOperator::Lte(v) => write!(f, "lower than or equal to {v}"),
+      HarrawOperator::Gte(v) => write!(f, "greater than or equal to {v}"),
+      HarrawOperator::Contains(v) => write!(f, "containing `{}`", hrw_value_to_string(v)),
+      HarrawOperator::Matches(r) => write!(f, "matching /{r}/"),
+      HarrawOperator::In(v) => write!(f, "in {}", serde_json::to_string(v).unwrap()),
+      HarrawOperator::Exists(true) => write!(f, "present"),
+      HarrawOperator::Exists(false) => write!(f, "absent"),
+      HarrawOperator::Length(v) => write!(f, "of length {v}"),
+      HarrawOperator::Type(v) => write!(f, "of type {v}"),
+      HarrawOperator::Schema(v) => write!(f, "matching schema {v}"),
+    }
+  }
+}
+
+impl fmt::Display for HarrawAssertion {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let operators: Vec<String> = self.operators.iter().map(|o| o.to_string()).collect();
+    write!(f, "{}", operators.join(" and "))
+  }
+}
+
+fn hrw_expected_number(name: &str, expected: &Yaml) -> f64 {
+  expected.as_i64().map(|v| v as f64).or_else(|| expected.as_f64()).unwrap_or_else(|| panic!("`{}` needs to be a number", name))
+}
+
+fn hrw_actual_number(value: &Value) -> Result<f64, String> {
+  match value {
+    Value::Number(n) => Ok(n.as_f64().unwrap()),
+    Value::String(s) => s.trim().parse::<f64>().map_err(|_| format!("`{s}` is not a number")),
+    _ => Err(format!("`{}` is not a number", hrw_value_to_string(value))),
+  }
+}
+
+/// Numbers are compared by value when either side is a number, anything else by its rendered string.
+fn hrw_loose_eq(actual: &Value, expected: &Value) -> bool {
+  if actual.is_number() || expected.is_number() {
+    if let (Ok(a), Ok(e)) = (hrw_actual_number(actual), hrw_actual_number(expected)) {
+      return a == e;
+    }
+  }
+  hrw_value_to_string(actual) == hrw_value_to_string(expected)
+}
+
+fn hrw_type_of(value: &Value) -> &'static str {
+  match value {
+    Value::Null => "null",
+    Value::Bool(_) => "boolean",
+    Value::Number(n) if n.is_i64() || n.is_u64() => "integer",
+    Value::Number(_) => "number",
+    Value::String(_) => "string",
+    Value::Array(_) => "array",
+    Value::Object(_) => "object",
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serde_json::json;
+
+  fn hrw_assertion(text: &str) -> HarrawAssertion {
+    let docs = yaml_rust::YamlLoader::load_from_str(text).unwrap();
+    HarrawAssertion::new("benchmark.yml", &docs[0], &["key"])
+  }
+
+  #[test]
+  fn hrw_scalar_means_equals() {
+    let assertion = hrw_assertion("200");
+
+    assert!(assertion.hrw_evaluate(Some(&json!(200))).is_ok());
+    assert!(assertion.hrw_evaluate(Some(&json!("200"))).is_ok());
+    assert!(assertion.hrw_evaluate(Some(&json!(404))).is_err());
+  }
+
+  #[test]
+  fn hrw_value_is_an_alias_of_equals() {
+    let assertion = hrw_assertion("---\nkey: bar\nvalue: \"2\"");
+
+    assert!(assertion.hrw_evaluate(Some(&json!("2"))).is_ok());
+    assert!(assertion.hrw_evaluate(Some(&json!("3"))).is_err());
+  }
+
+  #[test]
+  fn hrw_numeric_operators_can_be_combined() {
+    let assertion = hrw_assertion("---\ngte: 200\nlt: 300");
+
+    assert!(assertion.hrw_evaluate(Some(&json!(200))).is_ok());
+    assert!(assertion.hrw_evaluate(Some(&json!("299.5"))).is_ok());
+    assert!(assertion.hrw_evaluate(Some(&json!(300))).is_err());
+    assert_eq!(assertion.hrw_evaluate(Some(&json!("abc"))), Err("`abc` is not a number".to_string()));
+  }
+
+  #[test]
+  fn hrw_contains_strings_arrays_and_objects() {
+    let assertion = hrw_assertion("---\ncontains: foo");
+
+    assert!(assertion.hrw_evaluate(Some(&json!("a foo b"))).is_ok());
+    assert!(assertion.hrw_evaluate(Some(&json!(["bar", "foo"]))).is_ok());
+    assert!(assertion.hrw_evaluate(Some(&json!({"foo": 1}))).is_ok());
+    assert!(assertion.hrw_evaluate(Some(&json!(["foobar"]))).is_err());
+  }
+
+  #[test]
+  fn hrw_matches_and_in() {
+    assert!(hrw_assertion("---\nmatches: '^\\+44 \\d+$'").hrw_evaluate(Some(&json!("+44 1234567"))).is_ok());
+    assert!(hrw_assertion("---\nin: [200, 201]").hrw_evaluate(Some(&json!(201))).is_ok());
+    assert!(hrw_assertion("---\nin: [200, 201]").hrw_evaluate(Some(&json!(204))).is_err());
+  }
+
+  #[test]
+  fn hrw_exists_and_not_exists() {
+    assert!(hrw_assertion("---\nexists: true").hrw_evaluate(Some(&json!(null))).is_ok());
+    assert!(hrw_assertion("---\nexists: true").hrw_evaluate(None).is_err());
+    assert!(hrw_assertion("---\nnot_exists: true").hrw_evaluate(None).is_ok());
+    assert!(hrw_assertion("---\nnot_exists: true").hrw_evaluate(Some(&json!(1))).is_err());
+    assert_eq!(hrw_assertion("---\nequals: 1").hrw_evaluate(None), Err("is missing".to_string()));
+  }
+
+  #[test]
+  fn hrw_length_and_type() {
+    assert!(hrw_assertion("---\nlength: 3").hrw_evaluate(Some(&json!([1, 2, 3]))).is_ok());
+    assert!(hrw_assertion("---\nlength: 3").hrw_evaluate(Some(&json!("abcd"))).is_err());
+    assert!(hrw_assertion("---\ntype: integer").hrw_evaluate(Some(&json!(3))).is_ok());
+    assert!(hrw_assertion("---\ntype: number").hrw_evaluate(Some(&json!(3))).is_ok());
+    assert!(hrw_assertion("---\ntype: list").hrw_evaluate(Some(&json!({}))).is_err());
+  }
+
+  #[test]
+  fn hrw_schema_reports_the_failing_pointer() {
+    let assertion = hrw_assertion("---\nschema: ./fixtures/user.schema.json");
+
+    assert!(assertion.hrw_evaluate(Some(&json!({"id": 1, "name": "Mary"}))).is_ok());
+    assert_eq!(
+      assertion.hrw_evaluate(Some(&json!({"id": 1, "name": 3}))),
+      Err("does not match schema ./fixtures/user.schema.json: /name: 3 is not of type \"string\"".to_string())
+    );
+  }
+
+  #[test]
+  #[should_panic(expected = "Unknown assertion operator `between`")]
+  fn hrw_unknown_operator() {
+    hrw_assertion("---\nbetween: 3");
+  }
+}
@@ -5,8 +5,8 @@ use crate::interpolator::INTERPOLATION_REGEX;
 
 use crate::service;
 use crate::benchmark::Benchmark;
-use crate::backend::{include, multi_csv_request, multi_file_request, multi_iter_request, multi_request};
-use crate::tags::HarrawTags;
+use crate::backend::{include, multi_csv_request, multi_file_request, multi_iter_request, multi_json_request, multi_request};
+use crate::tags::{self, HarrawTags};
 
 use crate::reader;
 
@@ -15,6 +15,24 @@ pub fn hrw_is_that_you(item: &Yaml) -> bool {
 }
 
 pub fn hrw_expand(parent_path: &str, item: &Yaml, benchmark: &mut Benchmark, tags: &HarrawTags) {
+  let (final_path, items) = hrw_read_included(parent_path, item);
+
+  let mut included = Benchmark::new();
+  hrw_expand_items(&final_path, &items, &mut included, tags);
+
+  if included.is_empty() {
+    return;
+  }
+
+  if item["vars"].is_badvalue() {
+    benchmark.extend(included);
+  } else {
+    benchmark.push(Box::new(service::scope::HarrawScope::new(&item["vars"], included)));
+  }
+}
+
+/// Reads the items of an included file, with the `prefix` and `tags` of the include applied to them.
+fn hrw_read_included(parent_path: &str, item: &Yaml) -> (String, Vec<Yaml>) {
   let include_path = item["include"].as_str().unwrap();
 
   if INTERPOLATION_REGEX.is_match(include_path) {
@@ -22,56 +40,164 @@ pub fn hrw_expand(parent_path: &str, item: &Yaml, benchmark: &mut Benchmark, tag
   }
 
   let include_filepath = Path::new(parent_path).with_file_name(include_path);
-  let final_path = include_filepath.to_str().unwrap();
+  let final_path = include_filepath.to_str().unwrap().to_string();
+
+  let docs = reader::hrw_read_file_as_yml(&final_path);
+  let items = reader::hrw_read_yaml_doc_accessor(&docs[0], None);
+  let items = match item["prefix"].as_str() {
+    Some(prefix) => hrw_prefix_names(items, prefix),
+    None => items.to_vec(),
+  };
+
+  (final_path, tags::hrw_inherit_tags(&items, item))
+}
+
+/// The plan items as they get expanded: included files are read and blocks opened,
+/// passing their tags on to the nested items.
+pub fn hrw_walk_items(parent_path: &str, items: &[Yaml]) -> Vec<Yaml> {
+  let mut walked = Vec::new();
 
-  hrw_expand_from_filepath(final_path, benchmark, None, tags);
+  for item in items {
+    if include::hrw_is_that_you(item) {
+      let (final_path, included) = hrw_read_included(parent_path, item);
+      walked.extend(hrw_walk_items(&final_path, &included));
+    } else if service::block::HarrawBlock::hrw_is_that_you(item) {
+      walked.extend(hrw_walk_items(parent_path, &tags::hrw_inherit_tags(service::block::HarrawBlock::hrw_items(item), item)));
+    } else if service::parallel::HarrawParallel::hrw_is_that_you(item) {
+      walked.extend(hrw_walk_items(parent_path, &tags::hrw_inherit_tags(service::parallel::HarrawParallel::hrw_items(item), item)));
+    } else {
+      walked.push(item.clone());
+    }
+  }
+  walked
+}
+
+/// Prepends `prefix` to the names of the included items, including the steps of
+/// their blocks and parallel blocks, and the items of nested includes.
+fn hrw_prefix_names(items: &[Yaml], prefix: &str) -> Vec<Yaml> {
+  let mut items = items.to_vec();
+
+  for item in items.iter_mut() {
+    if let Yaml::Hash(hash) = item {
+      if let Some(Yaml::String(name)) = hash.get_mut(&Yaml::String("name".to_string())) {
+        name.insert_str(0, prefix);
+      }
+
+      for key in ["block", "group", "parallel"] {
+        if let Some(Yaml::Array(nested)) = hash.get(&Yaml::String(key.to_string())) {
+          let nested = hrw_prefix_names(nested, prefix);
+          hash.insert(Yaml::String(key.to_string()), Yaml::Array(nested));
+        }
+      }
+
+      if hash.contains_key(&Yaml::String("include".to_string())) {
+        let key = Yaml::String("prefix".to_string());
+        let nested_prefix = format!("{}{}", prefix, hash.get(&key).and_then(Yaml::as_str).unwrap_or_default());
+        hash.insert(key, Yaml::String(nested_prefix));
+      }
+    }
+  }
+  items
 }
 
 pub fn hrw_expand_from_filepath(parent_path: &str, benchmark: &mut Benchmark, accessor: Option<&str>, tags: &HarrawTags) {
   let docs = reader::hrw_read_file_as_yml(parent_path);
   let items = reader::hrw_read_yaml_doc_accessor(&docs[0], accessor);
 
+  hrw_expand_items(parent_path, items, benchmark, tags);
+}
+
+/// Expands plan items into runnables, wrapping them with their `until` and `when` conditions.
+pub fn hrw_expand_items(parent_path: &str, items: &[Yaml], benchmark: &mut Benchmark, tags: &HarrawTags) {
   for item in items {
+    let start = benchmark.len();
+
+    // Includes and blocks are selected through the tags their nested items inherit
     if include::hrw_is_that_you(item) {
       include::hrw_expand(parent_path, item, benchmark, tags);
+    } else if service::block::HarrawBlock::hrw_is_that_you(item) {
+      let nested = hrw_inherit(&tags::hrw_inherit_tags(service::block::HarrawBlock::hrw_items(item), item), item, "on_error");
+      let mut items = Benchmark::new();
+      hrw_expand_items(parent_path, &nested, &mut items, tags);
 
-      continue;
-    }
+      if !items.is_empty() {
+        benchmark.push(Box::new(service::block::HarrawBlock::new(item, items)));
+      }
+    } else if service::parallel::HarrawParallel::hrw_is_that_you(item) {
+      let nested = hrw_inherit(&tags::hrw_inherit_tags(service::parallel::HarrawParallel::hrw_items(item), item), item, "on_error");
+      let mut items = Benchmark::new();
+      hrw_expand_items(parent_path, &nested, &mut items, tags);
 
-    if tags.hrw_should_skip_item(item) {
+      if !items.is_empty() {
+        benchmark.push(Box::new(service::parallel::HarrawParallel::new(item, items)));
+      }
+    } else if tags.hrw_should_skip_item(item) {
       continue;
-    }
-    if multi_request::hrw_is_that_you(item) {
-      multi_request::hrw_expand(item, benchmark);
+    } else if multi_request::hrw_is_that_you(item) {
+      multi_request::hrw_expand(parent_path, item, benchmark);
     } else if multi_iter_request::hrw_is_that_you(item) {
-      multi_iter_request::hrw_expand(item, benchmark);
+      multi_iter_request::hrw_expand(parent_path, item, benchmark);
     } else if multi_csv_request::hrw_is_that_you(item) {
       multi_csv_request::hrw_expand(parent_path, item, benchmark);
     } else if multi_file_request::hrw_is_that_you(item) {
       multi_file_request::hrw_expand(parent_path, item, benchmark);
+    } else if multi_json_request::hrw_is_that_you(item) {
+      multi_json_request::hrw_expand(parent_path, item, benchmark);
+    } else if service::for_each::HarrawForEach::hrw_is_that_you(item) {
+      benchmark.push(Box::new(service::for_each::HarrawForEach::new(parent_path, item)));
     } else if service::delay::HarrawDelay::hrw_is_that_you(item) {
       benchmark.push(Box::new(service::delay::HarrawDelay::new(item, None)));
     } else if service::exec::HarrawExec::hrw_is_that_you(item) {
       benchmark.push(Box::new(service::exec::HarrawExec::new(item, None)));
     } else if service::assign::HarrawAssign::hrw_is_that_you(item) {
       benchmark.push(Box::new(service::assign::HarrawAssign::new(item, None)));
+    } else if service::share::HarrawShare::hrw_is_that_you(item) {
+      benchmark.push(Box::new(service::share::HarrawShare::new(item)));
     } else if service::assert::HarrawAssert::hrw_is_that_you(item) {
-      benchmark.push(Box::new(service::assert::HarrawAssert::new(item, None)));
+      benchmark.push(Box::new(service::assert::HarrawAssert::new(parent_path, item, None)));
     } else if service::request::HarrawRequest::hrw_is_that_you(item) {
-      benchmark.push(Box::new(service::request::HarrawRequest::new(item, None, None)));
+      benchmark.push(Box::new(service::request::HarrawRequest::new(parent_path, item, None, None)));
     } else {
       let mut out_str = String::new();
       let mut emitter = YamlEmitter::new(&mut out_str);
       emitter.dump(item).unwrap();
       panic!("Unknown node:\n\n{}\n\n", out_str);
     }
+
+    if benchmark.len() == start {
+      continue;
+    }
+
+    if service::until::HarrawUntil::hrw_is_that_you(item) {
+      let items = benchmark.split_off(start);
+      benchmark.push(Box::new(service::until::HarrawUntil::new(item, items)));
+    }
+
+    if service::when::HarrawWhen::hrw_is_that_you(item) {
+      let items = benchmark.split_off(start);
+      benchmark.push(Box::new(service::when::HarrawWhen::new(item, items)));
+    }
+  }
+}
+
+/// Copies a setting of a parent item, like the `on_error` of a block, into the nested items without their own.
+fn hrw_inherit(items: &[Yaml], parent: &Yaml, key: &str) -> Vec<Yaml> {
+  let mut items = items.to_vec();
+
+  if !parent[key].is_badvalue() {
+    for item in items.iter_mut() {
+      if let Yaml::Hash(hash) = item {
+        hash.entry(Yaml::String(key.to_string())).or_insert_with(|| parent[key].clone());
+      }
+    }
   }
+  items
 }
 
 #[cfg(test)]
 mod tests {
   use crate::benchmark::Benchmark;
-  use crate::backend::include::{hrw_expand, hrw_is_that_you};
+  use crate::backend::include::{hrw_expand, hrw_expand_from_filepath, hrw_inherit, hrw_is_that_you, hrw_prefix_names, hrw_walk_items};
   use crate::tags::HarrawTags;
 
   #[test]
@@ -81,12 +207,92 @@ mod tests {
     let doc = &docs[0];
     let mut benchmark: Benchmark = Benchmark::new();
 
-    hrw_expand("example/benchmark.yml", doc, &mut benchmark, &HarrawTags::new(None, None));
+    hrw_expand("benchmark.yml", doc, &mut benchmark, &HarrawTags::new(None, None));
 
     assert!(hrw_is_that_you(doc));
     assert_eq!(benchmark.len(), 2);
   }
 
+  #[test]
+  fn hrw_expand_guarded_items() {
+    let mut benchmark: Benchmark = Benchmark::new();
+
+    hrw_expand_from_filepath("./fixtures/when.yml", &mut benchmark, None, &HarrawTags::new(None, None));
+
+    assert_eq!(benchmark.len(), 4);
+  }
+
+  #[test]
+  fn hrw_expand_blocks_with_their_tags() {
+    let mut benchmark: Benchmark = Benchmark::new();
+    hrw_expand_from_filepath("./fixtures/when.yml", &mut benchmark, None, &HarrawTags::new(Some("checkout"), Some("payment")));
+
+    assert_eq!(benchmark.len(), 1);
+
+    let mut benchmark: Benchmark = Benchmark::new();
+    hrw_expand_from_filepath("./fixtures/when.yml", &mut benchmark, None, &HarrawTags::new(None, Some("checkout")));
+
+    assert_eq!(benchmark.len(), 3);
+  }
+
+  #[test]
+  fn hrw_expand_tagged_include() {
+    let text = "---\nname: Include comment\ninclude: comments.yml\ntags: [comments]";
+    let docs = yaml_rust::YamlLoader::load_from_str(text).unwrap();
+
+    let mut benchmark: Benchmark = Benchmark::new();
+    hrw_expand("benchmark.yml", &docs[0], &mut benchmark, &HarrawTags::new(Some("comments"), None));
+    assert_eq!(benchmark.len(), 2);
+
+    let mut benchmark: Benchmark = Benchmark::new();
+    hrw_expand("benchmark.yml", &docs[0], &mut benchmark, &HarrawTags::new(None, Some("comments")));
+    assert_eq!(benchmark.len(), 0);
+  }
+
+  #[test]
+  fn hrw_walks_includes_and_blocks() {
+    let text = "---\n- name: Comments\n  include: comments.yml\n  tags: [comments]\n- name: Flow\n  tags: [flow]\n  block:\n    - name: Pay\n      tags: [payment]\n      request:\n        url: /pay";
+    let docs = yaml_rust::YamlLoader::load_from_str(text).unwrap();
+    let items = hrw_walk_items("benchmark.yml", docs[0].as_vec().unwrap());
+    let names: Vec<&str> = items.iter().map(|item| item["name"].as_str().unwrap()).collect();
+
+    assert_eq!(names, vec!["Fetch comments", "Fetch sub comments", "Pay"]);
+    assert_eq!(items[1]["tags"][0].as_str(), Some("comments"));
+    assert_eq!(items[2]["tags"].as_vec().unwrap().len(), 2);
+  }
+
+  #[test]
+  fn hrw_expand_parameterised_include() {
+    let text = "---\nname: Include comment\ninclude: comments.yml\nprefix: \"Admin \"\nvars:\n  user: admin";
+    let docs = yaml_rust::YamlLoader::load_from_str(text).unwrap();
+    let mut benchmark: Benchmark = Benchmark::new();
+
+    hrw_expand("benchmark.yml", &docs[0], &mut benchmark, &HarrawTags::new(None, None));
+
+    assert_eq!(benchmark.len(), 1);
+  }
+
+  #[test]
+  fn hrw_prefixes_nested_names() {
+    let text = "---\n- name: Fetch\n  request:\n    url: /\n- name: Flow\n  block:\n    - name: Pay\n      request:\n        url: /pay\n- name: Nested\n  include: comments.yml\n  prefix: \"web \"";
+    let docs = yaml_rust::YamlLoader::load_from_str(text).unwrap();
+    let items = hrw_prefix_names(docs[0].as_vec().unwrap(), "admin ");
+
+    assert_eq!(items[0]["name"].as_str(), Some("admin Fetch"));
+    assert_eq!(items[1]["block"][0]["name"].as_str(), Some("admin Pay"));
+    assert_eq!(items[2]["prefix"].as_str(), Some("admin web "));
+  }
+
+  #[test]
+  fn hrw_inherits_the_block_on_error() {
+    let text = "---\non_error: abort_run\nblock:\n  - name: Cart\n    request:\n      url: /api/cart\n  - name: Pay\n    on_error: continue\n    request:\n      url: /api/pay";
+    let docs = yaml_rust::YamlLoader::load_from_str(text).unwrap();
+    let items = hrw_inherit(docs[0]["block"].as_vec().unwrap(), &docs[0], "on_error");
+
+    assert_eq!(items[0]["on_error"].as_str(), Some("abort_run"));
+    assert_eq!(items[1]["on_error"].as_str(), Some("continue"));
+  }
+
   #[test]
   #[should_panic]
   fn hrw_invalid_expand() {
@@ -95,6 +301,6 @@ mod tests {
     let doc = &docs[0];
     let mut benchmark: Benchmark = Benchmark::new();
 
-    hrw_expand("example/benchmark.yml", doc, &mut benchmark, &HarrawTags::new(None, None));
+    hrw_expand("benchmark.yml", doc, &mut benchmark, &HarrawTags::new(None, None));
   }
 }
\ No newline at end of file
@@ -2,6 +2,7 @@ pub mod include;
 pub mod multi_csv_request;
 pub mod multi_file_request;
 pub mod multi_iter_request;
+pub mod multi_json_request;
 pub mod multi_request;
 
 use yaml_rust::Yaml;
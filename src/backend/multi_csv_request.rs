@@ -1,5 +1,5 @@
 use rand::seq::SliceRandom;
-use rand::thread_rng;
+use crate::random::hrw_with_rng;
 use std::path::Path;
 use yaml_rust::Yaml;
 
@@ -36,8 +36,7 @@ pub fn hrw_expand(parent_path: &str, item: &Yaml, benchmark: &mut Benchmark) {
 
   if let Some(shuffle) = item["shuffle"].as_bool() {
     if shuffle {
-      let mut rng = thread_rng();
-      with_items_file.shuffle(&mut rng);
+      hrw_with_rng(|rng| with_items_file.shuffle(rng));
     }
   }
 
@@ -45,7 +44,7 @@ pub fn hrw_expand(parent_path: &str, item: &Yaml, benchmark: &mut Benchmark) {
   for (index, with_item) in with_items_file.iter().take(pick).enumerate() {
     let index = index as u32;
 
-    benchmark.push(Box::new(HarrawRequest::new(item, Some(with_item.clone()), Some(index))));
+    benchmark.push(Box::new(HarrawRequest::new(parent_path, item, Some(with_item.clone()), Some(index))));
   }
 }
 
@@ -60,7 +59,7 @@ mod tests {
     let doc = &docs[0];
     let mut benchmark: Benchmark = Benchmark::new();
 
-    hrw_expand("example/benchmark.yml", doc, &mut benchmark);
+    hrw_expand("benchmark.yml", doc, &mut benchmark);
 
     assert!(hrw_is_that_you(doc));
     assert_eq!(benchmark.len(), 2);
@@ -73,7 +72,7 @@ mod tests {
     let doc = &docs[0];
     let mut benchmark: Benchmark = Benchmark::new();
 
-    hrw_expand("example/benchmark.yml", doc, &mut benchmark);
+    hrw_expand("benchmark.yml", doc, &mut benchmark);
 
     assert!(hrw_is_that_you(doc));
     assert_eq!(benchmark.len(), 2);
@@ -86,7 +85,7 @@ mod tests {
     let doc = &docs[0];
     let mut benchmark: Benchmark = Benchmark::new();
 
-    hrw_expand("example/benchmark.yml", doc, &mut benchmark);
+    hrw_expand("benchmark.yml", doc, &mut benchmark);
 
     assert!(hrw_is_that_you(doc));
     assert_eq!(benchmark.len(), 1);
@@ -100,6 +99,6 @@ mod tests {
     let doc = &docs[0];
     let mut benchmark: Benchmark = Benchmark::new();
 
-    hrw_expand("example/benchmark.yml", doc, &mut benchmark);
+    hrw_expand("benchmark.yml", doc, &mut benchmark);
   }
 }
\ No newline at end of file
@@ -4,7 +4,7 @@ use crate::benchmark::Benchmark;
 use crate::interpolator::INTERPOLATION_REGEX;
 use crate::reader;
 use rand::seq::SliceRandom;
-use rand::thread_rng;
+use crate::random::hrw_with_rng;
 use std::path::Path;
 use yaml_rust::Yaml;
 
@@ -30,8 +30,7 @@ pub fn hrw_expand(parent_path: &str, item: &Yaml, benchmark: &mut Benchmark) {
 
   if let Some(shuffle) = item["shuffle"].as_bool() {
     if shuffle {
-      let mut rng = thread_rng();
-      with_items_file.shuffle(&mut rng);
+      hrw_with_rng(|rng| with_items_file.shuffle(rng));
     }
   }
 
@@ -39,7 +38,7 @@ pub fn hrw_expand(parent_path: &str, item: &Yaml, benchmark: &mut Benchmark) {
   for (index, with_item) in with_items_file.iter().take(pick).enumerate() {
     let index = index as u32;
 
-    benchmark.push(Box::new(HarrawRequest::new(item, Some(with_item.clone()), Some(index))));
+    benchmark.push(Box::new(HarrawRequest::new(parent_path, item, Some(with_item.clone()), Some(index))));
   }
 }
 
@@ -54,7 +53,7 @@ mod test {
     let doc = &docs[0];
     let mut benchmark: Benchmark = Benchmark::new();
 
-    hrw_expand("example/benchmark.yml", doc, &mut benchmark);
+    hrw_expand("benchmark.yml", doc, &mut benchmark);
 
     assert!(hrw_is_that_you(doc));
     assert_eq!(benchmark.len(), 3);
@@ -67,7 +66,7 @@ mod test {
     let doc = &docs[0];
     let mut benchmark: Benchmark = Benchmark::new();
 
-    hrw_expand("example/benchmark.yml", doc, &mut benchmark);
+    hrw_expand("benchmark.yml", doc, &mut benchmark);
 
     assert!(hrw_is_that_you(doc));
     assert_eq!(benchmark.len(), 2);
@@ -80,7 +79,7 @@ mod test {
     let doc = &docs[0];
     let mut benchmark: Benchmark = Benchmark::new();
 
-    hrw_expand("example/benchmark.yml", doc, &mut benchmark);
+    hrw_expand("benchmark.yml", doc, &mut benchmark);
 
     assert!(hrw_is_that_you(doc));
     assert_eq!(benchmark.len(), 1);
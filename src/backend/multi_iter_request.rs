@@ -1,7 +1,7 @@
 use std::convert::TryInto;
 
 use rand::seq::SliceRandom;
-use rand::thread_rng;
+use crate::random::hrw_with_rng;
 use yaml_rust::Yaml;
 
 use crate::interpolator::INTERPOLATION_REGEX;
@@ -13,7 +13,7 @@ pub fn hrw_is_that_you(item: &Yaml) -> bool {
   item["request"].as_hash().is_some() && item["with_items_range"].as_hash().is_some()
 }
 
-pub fn hrw_expand(item: &Yaml, benchmark: &mut Benchmark) {
+pub fn hrw_expand(parent_path: &str, item: &Yaml, benchmark: &mut Benchmark) {
   if let Some(with_iter_items) = item["with_items_range"].as_hash() {
     let init = Yaml::Integer(1);
     let lstart = Yaml::String("start".into());
@@ -49,8 +49,7 @@ pub fn hrw_expand(item: &Yaml, benchmark: &mut Benchmark) {
 
       if let Some(shuffle) = item["shuffle"].as_bool() {
         if shuffle {
-          let mut rng = thread_rng();
-          with_items.shuffle(&mut rng);
+          hrw_with_rng(|rng| with_items.shuffle(rng));
         }
       }
 
@@ -61,7 +60,7 @@ pub fn hrw_expand(item: &Yaml, benchmark: &mut Benchmark) {
       for (index, value) in with_items.iter().enumerate() {
         let index = index as u32;
 
-        benchmark.push(Box::new(HarrawRequest::new(item, Some(Yaml::Integer(*value)), Some(index))));
+        benchmark.push(Box::new(HarrawRequest::new(parent_path, item, Some(Yaml::Integer(*value)), Some(index))));
       }
     }
   }
@@ -78,7 +77,7 @@ mod tests {
     let doc = &docs[0];
     let mut benchmark: Benchmark = Benchmark::new();
 
-    hrw_expand(doc, &mut benchmark);
+    hrw_expand("benchmark.yml", doc, &mut benchmark);
 
     assert!(hrw_is_that_you(doc));
     assert_eq!(benchmark.len(), 10);
@@ -91,7 +90,7 @@ mod tests {
     let doc = &docs[0];
     let mut benchmark: Benchmark = Benchmark::new();
 
-    hrw_expand(doc, &mut benchmark);
+    hrw_expand("benchmark.yml", doc, &mut benchmark);
 
     assert!(hrw_is_that_you(doc));
     assert_eq!(benchmark.len(), 3);
@@ -105,7 +104,7 @@ mod tests {
     let doc = &docs[0];
     let mut benchmark: Benchmark = Benchmark::new();
 
-    hrw_expand(doc, &mut benchmark);
+    hrw_expand("benchmark.yml", doc, &mut benchmark);
   }
 
   #[test]
@@ -116,6 +115,6 @@ mod tests {
     let doc = &docs[0];
     let mut benchmark: Benchmark = Benchmark::new();
 
-    hrw_expand(doc, &mut benchmark);
+    hrw_expand("benchmark.yml", doc, &mut benchmark);
   }
 }
\ No newline at end of file
@@ -0,0 +1,89 @@
+use super::hrw_pick;
+use crate::service::request::HarrawRequest;
+use crate::benchmark::Benchmark;
+use crate::interpolator::INTERPOLATION_REGEX;
+use crate::reader;
+use rand::seq::SliceRandom;
+use crate::random::hrw_with_rng;
+use std::path::Path;
+use yaml_rust::Yaml;
+
+pub fn hrw_is_that_you(item: &Yaml) -> bool {
+  item["request"].as_hash().is_some() && (item["with_items_from_json"].as_str().is_some() || item["with_items_from_jsonl"].as_str().is_some())
+}
+
+pub fn hrw_expand(parent_path: &str, item: &Yaml, benchmark: &mut Benchmark) {
+  let (property, with_items_path) = if let Some(with_items_path) = item["with_items_from_json"].as_str() {
+    ("with_items_from_json", with_items_path)
+  } else if let Some(with_items_path) = item["with_items_from_jsonl"].as_str() {
+    ("with_items_from_jsonl", with_items_path)
+  } else {
+    unreachable!();
+  };
+
+  if INTERPOLATION_REGEX.is_match(with_items_path) {
+    panic!("Interpolation not supported in '{}' property!", property);
+  }
+
+  let with_items_filepath = Path::new(parent_path).with_file_name(with_items_path);
+  let final_path = with_items_filepath.to_str().unwrap();
+
+  let mut with_items_file = if property == "with_items_from_json" {
+    reader::hrw_read_json_file_as_yml(final_path)
+  } else {
+    reader::hrw_read_jsonl_file_as_yml(final_path)
+  };
+
+  if let Some(shuffle) = item["shuffle"].as_bool() {
+    if shuffle {
+      hrw_with_rng(|rng| with_items_file.shuffle(rng));
+    }
+  }
+
+  let pick = hrw_pick(item, &with_items_file);
+  for (index, with_item) in with_items_file.iter().take(pick).enumerate() {
+    let index = index as u32;
+
+    benchmark.push(Box::new(HarrawRequest::new(parent_path, item, Some(with_item.clone()), Some(index))));
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn hrw_expand_multi_json() {
+    let text = "---\nname: foobar\nrequest:\n  url: /api/{{ item.address.city }}\nwith_items_from_json: ./fixtures/accounts.json";
+    let docs = yaml_rust::YamlLoader::load_from_str(text).unwrap();
+    let doc = &docs[0];
+    let mut benchmark: Benchmark = Benchmark::new();
+
+    hrw_expand("benchmark.yml", doc, &mut benchmark);
+
+    assert!(hrw_is_that_you(doc));
+    assert_eq!(benchmark.len(), 3);
+  }
+
+  #[test]
+  fn hrw_expand_multi_jsonl_should_work_with_pick_and_shuffle() {
+    let text = "---\nname: foobar\nrequest:\n  url: /api/{{ item.email }}\npick: 1\nshuffle: true\nwith_items_from_jsonl: ./fixtures/accounts.jsonl";
+    let docs = yaml_rust::YamlLoader::load_from_str(text).unwrap();
+    let doc = &docs[0];
+    let mut benchmark: Benchmark = Benchmark::new();
+
+    hrw_expand("benchmark.yml", doc, &mut benchmark);
+
+    assert!(hrw_is_that_you(doc));
+    assert_eq!(benchmark.len(), 1);
+  }
+
+  #[test]
+  fn hrw_json_keeps_nested_structure() {
+    let items = reader::hrw_read_json_file_as_yml("./fixtures/accounts.json");
+
+    assert_eq!(items[0]["address"]["city"].as_str(), Some("Lisbon"));
+    assert_eq!(items[0]["roles"][1].as_str(), Some("dev"));
+    assert_eq!(items[2]["score"].as_f64(), Some(7.5));
+  }
+}
@@ -1,5 +1,5 @@
 use rand::seq::SliceRandom;
-use rand::thread_rng;
+use crate::random::hrw_with_rng;
 use yaml_rust::Yaml;
 
 use super::hrw_pick;
@@ -11,14 +11,13 @@ pub fn hrw_is_that_you(item: &Yaml) -> bool {
   item["request"].as_hash().is_some() && item["with_items"].as_vec().is_some()
 }
 
-pub fn hrw_expand(item: &Yaml, benchmark: &mut Benchmark) {
+pub fn hrw_expand(parent_path: &str, item: &Yaml, benchmark: &mut Benchmark) {
   if let Some(with_items) = item["with_items"].as_vec() {
     let mut with_items_list = with_items.clone();
 
     if let Some(shuffle) = item["shuffle"].as_bool() {
       if shuffle {
-        let mut rng = thread_rng();
-        with_items_list.shuffle(&mut rng);
+        hrw_with_rng(|rng| with_items_list.shuffle(rng));
       }
     }
 
@@ -32,7 +31,7 @@ pub fn hrw_expand(item: &Yaml, benchmark: &mut Benchmark) {
         panic!("Interpolations not supported in 'with_items' children!");
       }
 
-      benchmark.push(Box::new(HarrawRequest::new(item, Some(with_item.clone()), Some(index))));
+      benchmark.push(Box::new(HarrawRequest::new(parent_path, item, Some(with_item.clone()), Some(index))));
     }
   }
 }
@@ -48,7 +47,7 @@ mod tests {
     let doc = &docs[0];
     let mut benchmark: Benchmark = Benchmark::new();
 
-    hrw_expand(doc, &mut benchmark);
+    hrw_expand("benchmark.yml", doc, &mut benchmark);
 
     assert!(hrw_is_that_you(doc));
     assert_eq!(benchmark.len(), 3);
@@ -61,7 +60,7 @@ mod tests {
     let doc = &docs[0];
     let mut benchmark: Benchmark = Benchmark::new();
 
-    hrw_expand(doc, &mut benchmark);
+    hrw_expand("benchmark.yml", doc, &mut benchmark);
 
     assert!(hrw_is_that_you(doc));
     assert_eq!(benchmark.len(), 2);
@@ -74,7 +73,7 @@ mod tests {
     let doc = &docs[0];
     let mut benchmark: Benchmark = Benchmark::new();
 
-    hrw_expand(doc, &mut benchmark);
+    hrw_expand("benchmark.yml", doc, &mut benchmark);
 
     assert!(hrw_is_that_you(doc));
     assert_eq!(benchmark.len(), 1);
@@ -88,6 +87,6 @@ mod tests {
     let doc = &docs[0];
     let mut benchmark: Benchmark = Benchmark::new();
 
-    hrw_expand(doc, &mut benchmark);
+    hrw_expand("benchmark.yml", doc, &mut benchmark);
   }
 }
\ No newline at end of file
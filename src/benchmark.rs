@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 use futures::stream::{self, StreamExt};
@@ -6,41 +7,107 @@ use serde_json::{json, Map, Value};
 use tokio::{runtime, time::sleep};
 use reqwest::Client;
 use colored::*;
+use yaml_rust::Yaml;
 
-use crate::service::{HarrawReport, HarrawRunnable};
+use crate::service::{HarrawFlow, HarrawReports, HarrawRunnable};
 use crate::config::HarrawConfig;
 use crate::backend::include;
+use crate::feeder::{self, HarrawFeeder};
+use crate::random;
+use crate::reader;
 use crate::tags::HarrawTags;
 use crate::writer;
 
 
-pub type Benchmark = Vec<Box<(dyn HarrawRunnable + Sync + Send)>>;
+pub type Benchmark = Vec<Box<dyn HarrawRunnable + Sync + Send>>;
 pub type Context = Map<String, Value>;
-pub type Reports = Vec<HarrawReport>;
+pub type Reports = HarrawReports;
 pub type PoolStore = HashMap<String, Client>;
 pub type Pool = Arc<Mutex<PoolStore>>;
+pub type Abort = Arc<AtomicBool>;
+
+/// Runs a future on a single threaded runtime, for unit tests.
+#[cfg(test)]
+pub fn hrw_test_block_on<F: std::future::Future>(future: F) -> F::Output {
+    runtime::Builder::new_current_thread().enable_time().build().unwrap().block_on(future)
+}
+
+/// An empty client pool, for unit tests.
+#[cfg(test)]
+pub fn hrw_test_pool() -> Pool {
+    Arc::new(Mutex::new(PoolStore::new()))
+}
 
 pub struct HarrawBenchmarkResult {
   pub reports: Vec<Reports>,
   pub duration: f64,
+  pub aborted: bool,
+  pub seed: u64,
+}
+
+
+
+/// Runs the `setup` or `teardown` items once, outside of the measured load. Their reports are dropped.
+async fn hrw_run_phase(items: &Benchmark, context: &mut Context, pool: &Pool, config: &HarrawConfig) -> HarrawFlow {
+    let mut reports: Reports = Reports::default();
+
+    for item in items.iter() {
+        let flow = item.hrw_execute(context, &mut reports, pool, config).await;
+
+        if flow != HarrawFlow::Continue {
+            return flow;
+        }
+    }
+    HarrawFlow::Continue
 }
 
+/// Expands a `setup` or `teardown` section. Only `--skip-tags` applies to them.
+fn hrw_expand_phase(benchmark_path: &str, doc: &Yaml, tags: &HarrawTags) -> Benchmark {
+    let mut phase = Benchmark::new();
 
+    match doc {
+        Yaml::BadValue => {}
+        Yaml::Array(items) => include::hrw_expand_items(benchmark_path, items, &mut phase, &tags.hrw_within_selected()),
+        _ => panic!("`setup` and `teardown` need to be lists of plan items"),
+    }
+    phase
+}
 
-async fn hrw_run_iteration(benchmark: Arc<Benchmark>, pool: Pool, config: Arc<HarrawConfig>, iterations: i64) -> Vec<HarrawReport> {
+async fn hrw_run_iteration(benchmark: Arc<Benchmark>, pool: Pool, config: Arc<HarrawConfig>, initial: Arc<Context>, feeders: Arc<Vec<HarrawFeeder>>, abort: Abort, iterations: i64) -> Reports {
     if config.rampup > 0 {
         let delay = config.rampup / config.iterations;
         sleep(Duration::new((delay * iterations) as u64, 0)).await;
       }
     
-      let mut context: Context = Context::new();
-      let mut reports: Vec<HarrawReport> = Vec::new();
+      let mut context: Context = (*initial).clone();
+      let mut reports: Reports = Reports::default();
+
+      if abort.load(Ordering::Relaxed) {
+        return reports;
+      }
+
+      for feeder in feeders.iter() {
+        if !feeder.hrw_feed(iterations as usize, &mut context, config.quiet) {
+          return reports;
+        }
+      }
     
       context.insert("iterations".to_string(), json!(iterations.to_string()));
       context.insert("base".to_string(), json!(config.base.to_string()));
     
       for item in benchmark.iter() {
-        item.hrw_execute(&mut context, &mut reports, &pool, &config).await;
+        match item.hrw_execute(&mut context, &mut reports, &pool, &config).await {
+          HarrawFlow::Continue => {}
+          HarrawFlow::AbortIteration => break,
+          HarrawFlow::AbortRun => {
+            abort.store(true, Ordering::Relaxed);
+            break;
+          }
+        }
+
+        if abort.load(Ordering::Relaxed) {
+          break;
+        }
       }
       reports
 }   
@@ -52,8 +119,14 @@ fn hrw_join<S: ToString>(l: Vec<S>, sep: &str) -> String {
 
 
 #[allow(clippy::too_many_arguments)]
-pub fn hrw_execute(benchmark_path: &str, report_path_option: Option<&str>, relaxed_interpolations: bool, no_check_certificate: bool, quiet: bool, nanosec: bool, timeout: Option<&str>, verbose: bool, tags: &HarrawTags) -> HarrawBenchmarkResult {
-    let config = Arc::new(HarrawConfig::new(benchmark_path, relaxed_interpolations, no_check_certificate, quiet, nanosec, timeout.map_or(10, |t| t.parse().unwrap_or(10)), verbose));
+pub fn hrw_execute(benchmark_path: &str, report_path_option: Option<&str>, relaxed_interpolations: bool, no_check_certificate: bool, quiet: bool, nanosec: bool, timeout: Option<&str>, verbose: bool, seed: Option<u64>, overrides: &Context, environment: Option<&str>, tags: &HarrawTags) -> HarrawBenchmarkResult {
+    let config = Arc::new(HarrawConfig::new(benchmark_path, relaxed_interpolations, no_check_certificate, quiet, nanosec, timeout.map_or(10, |t| t.parse().unwrap_or(10)), verbose, seed, overrides, environment));
+    let seed = config.seed;
+    random::hrw_seed(seed);
+
+    if let Some(ref environment) = config.environment {
+        println!("{} {}", "Environment".yellow(), environment.purple());
+    }
 
     if report_path_option.is_some() {
         println!("{}: {}. Ignoring {} and {} properties...", "Report mode".yellow(), "on".purple(), "concurrency".yellow(), "iterations".yellow());
@@ -63,6 +136,7 @@ pub fn hrw_execute(benchmark_path: &str, report_path_option: Option<&str>, relax
         println!("{} {}", "Rampup".yellow(), config.rampup.to_string().purple());
     }
     println!("{} {}", "Base URL".yellow(), config.base.purple());
+    println!("{} {}", "Seed".yellow(), seed.to_string().purple());
     println!();
     let threads = std::cmp::min(num_cpus::get(), config.concurrency as usize);
     let rt = runtime::Builder::new_multi_thread().enable_all().worker_threads(threads).build().unwrap();
@@ -70,6 +144,10 @@ pub fn hrw_execute(benchmark_path: &str, report_path_option: Option<&str>, relax
         let mut benchmark: Benchmark = Benchmark::new();
         let pool_store: PoolStore = PoolStore::new();
         include::hrw_expand_from_filepath(benchmark_path, &mut benchmark, Some("plan"), tags);
+        let docs = reader::hrw_read_file_as_yml(benchmark_path);
+        let feeders = Arc::new(feeder::hrw_read_feeders(benchmark_path, &docs[0]["data"]));
+        let setup = hrw_expand_phase(benchmark_path, &docs[0]["setup"], tags);
+        let teardown = hrw_expand_phase(benchmark_path, &docs[0]["teardown"], tags);
 
         if benchmark.is_empty() {
             eprintln!("Empty benchmark. Exiting.");
@@ -77,22 +155,62 @@ pub fn hrw_execute(benchmark_path: &str, report_path_option: Option<&str>, relax
         }
         let benchmark = Arc::new(benchmark);
         let pool = Arc::new(Mutex::new(pool_store));
+        let abort: Abort = Arc::new(AtomicBool::new(false));
+
+        // The setup context, with everything it assigned, is where every iteration starts from
+        let mut context: Context = config.vars.clone();
+        context.insert("base".to_string(), json!(config.base.to_string()));
+
+        if hrw_run_phase(&setup, &mut context, &pool, &config).await != HarrawFlow::Continue {
+            eprintln!("{}", "Setup failed, skipping the load".red().bold());
+            abort.store(true, Ordering::Relaxed);
+        }
+        let initial = Arc::new(context.clone());
 
-        if let Some(report_path) = report_path_option {
-            let reports = hrw_run_iteration(benchmark.clone(), pool.clone(), config, 0).await;
-            writer::hrw_write_file(report_path, hrw_join(reports, ""));
+        let result = if let Some(report_path) = report_path_option {
+            let reports = random::hrw_scope_iteration(0, hrw_run_iteration(benchmark.clone(), pool.clone(), config.clone(), initial.clone(), feeders.clone(), abort.clone(), 0)).await;
+            writer::hrw_write_file(report_path, hrw_join(reports.requests, ""));
             HarrawBenchmarkResult {
                 reports: vec![],
                 duration: 0.0,
+                aborted: abort.load(Ordering::Relaxed),
+                seed,
             }
         } else {
-            let children = (0..config.iterations).map(|iteration| hrw_run_iteration(benchmark.clone(), pool.clone(), config.clone(), iteration));
+            let children = (0..config.iterations).map(|iteration| random::hrw_scope_iteration(iteration, hrw_run_iteration(benchmark.clone(), pool.clone(), config.clone(), initial.clone(), feeders.clone(), abort.clone(), iteration)));
             let buffered = stream::iter(children).buffer_unordered(config.concurrency as usize);
             let begin = Instant::now();
-            let reports: Vec<Vec<HarrawReport>> = buffered.collect::<Vec<_>>().await;
+            let reports: Vec<Reports> = buffered.collect::<Vec<_>>().await;
             let duration = begin.elapsed().as_secs_f64();
+            let aborted = abort.load(Ordering::Relaxed);
 
-            HarrawBenchmarkResult { reports, duration }
+            HarrawBenchmarkResult { reports, duration, aborted, seed }
+        };
+
+        if hrw_run_phase(&teardown, &mut context, &pool, &config).await != HarrawFlow::Continue {
+            eprintln!("{}", "Teardown failed".red().bold());
         }
+        result
     })
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hrw_setup_exports_its_context() {
+        let text = "---\nsetup:\n  - name: Token\n    assign:\n      key: token\n      value: secret\n  - name: Skipped\n    tags: [slow]\n    assign:\n      key: slow\n      value: \"yes\"";
+        let docs = yaml_rust::YamlLoader::load_from_str(text).unwrap();
+        let config = HarrawConfig::hrw_test(false);
+        let setup = hrw_expand_phase("benchmark.yml", &docs[0]["setup"], &HarrawTags::new(Some("fast"), Some("slow")));
+        let mut context = Context::new();
+
+        let flow = hrw_test_block_on(hrw_run_phase(&setup, &mut context, &hrw_test_pool(), &config));
+
+        assert_eq!(flow, HarrawFlow::Continue);
+        assert_eq!(setup.len(), 1);
+        assert_eq!(context.get("token"), Some(&json!("secret")));
+        assert!(hrw_expand_phase("benchmark.yml", &docs[0]["teardown"], &HarrawTags::new(None, None)).is_empty());
+    }
+}
@@ -4,11 +4,11 @@ use std::path::Path;
 use colored::*;
 use yaml_rust::YamlLoader;
 
-use crate::service::HarrawReport;
+use crate::benchmark::Reports;
 
 
 
-pub fn hrw_compare(list_reports: &[Vec<HarrawReport>], filepath: &str, threshold: &str) -> Result<(), i32> {
+pub fn hrw_compare(list_reports: &[Reports], filepath: &str, threshold: &str) -> Result<(), i32> {
     let threshold_value = match threshold.parse::<f64>() {
         Ok(v) => v,
         _ => panic!("arrrgh"),
@@ -31,7 +31,7 @@ pub fn hrw_compare(list_reports: &[Vec<HarrawReport>], filepath: &str, threshold
     println!();
 
     for report in list_reports {
-        for (i, report_item) in report.iter().enumerate() {
+        for (i, report_item) in report.requests.iter().enumerate() {
             let recorded_duration = items[i]["duration"].as_f64().unwrap();
             let delta_ms = report_item.duration - recorded_duration;
 
@@ -1,12 +1,17 @@
+use std::collections::HashMap;
+
+use serde_json::{json, Value};
 use yaml_rust::{Yaml, YamlLoader};
 
 use crate::benchmark::Context;
 use crate::interpolator;
 use crate::reader;
+use crate::service::{hrw_read_on_error, hrw_yaml_to_json, HarrawFlow};
 
 
 const NITERATIONS: i64 = 1;
 const NRAMPUP: i64 = 0;
+const ENVIRONMENT_SETTINGS: [&str; 8] = ["base", "vars", "headers", "concurrency", "iterations", "rampup", "seed", "on_error"];
 
 
 pub struct HarrawConfig{
@@ -20,25 +25,145 @@ pub struct HarrawConfig{
     pub nanosec: bool,
     pub timeout: u64,
     pub verbose: bool,
+    pub seed: u64,
+    pub vars: Context,
+    pub headers: HashMap<String, String>,
+    pub environment: Option<String>,
+    pub on_error: HarrawFlow,
 }
 
 
 impl HarrawConfig {
-    pub fn new(path: &str, relaxed_interpolations: bool, no_check_certificate: bool, quiet: bool, nanosec: bool, timeout: u64, verbose: bool) -> HarrawConfig {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(path: &str, relaxed_interpolations: bool, no_check_certificate: bool, quiet: bool, nanosec: bool, timeout: u64, verbose: bool, seed: Option<u64>, overrides: &Context, environment: Option<&str>) -> HarrawConfig {
         let config_file = reader::hrw_read_file(path);
         let config_docs = YamlLoader::load_from_str(config_file.as_str()).unwrap();
-        let config_doc = &config_docs[0];
-        let context: Context = Context::new();
-        let interpolator = interpolator::HarrawInterpolator::new(&context);
+        let config_doc = &hrw_apply_environment(&config_docs[0], environment);
+        let mut vars = hrw_read_vars_section(&config_doc["vars"], "vars");
+        vars.extend(overrides.clone());
+        let interpolator = interpolator::HarrawInterpolator::new(&vars);
         let iterations = hrw_read_i64_configuration(config_doc, &interpolator, "iterations", NITERATIONS);
         let concurrency = hrw_read_i64_configuration(config_doc, &interpolator, "concurrency", iterations);
         let rampup = hrw_read_i64_configuration(config_doc, &interpolator, "rampup", NRAMPUP);
         let base = hrw_read_str_configuration(config_doc, &interpolator, "base", "");
+        let seed = seed.or_else(|| hrw_read_seed_configuration(config_doc, &interpolator)).unwrap_or_else(rand::random);
+        let headers = hrw_read_headers_section(&config_doc["headers"]);
+        let environment = environment.map(str::to_string);
+        let on_error = hrw_read_on_error(config_doc).unwrap_or(HarrawFlow::Continue);
+
+        HarrawConfig { base,concurrency,iterations,relaxed_interpolations,no_check_certificate,rampup,quiet,nanosec,timeout,verbose,seed,vars,headers,environment,on_error }
+    }
+}
 
-        HarrawConfig { base,concurrency,iterations,relaxed_interpolations,no_check_certificate,rampup,quiet,nanosec,timeout,verbose }
+
+#[cfg(test)]
+impl HarrawConfig {
+    /// A quiet configuration for unit tests, without a benchmark file behind it.
+    pub fn hrw_test(relaxed_interpolations: bool) -> HarrawConfig {
+        HarrawConfig {
+            base: String::new(),
+            concurrency: 1,
+            iterations: 1,
+            relaxed_interpolations,
+            no_check_certificate: false,
+            rampup: 0,
+            quiet: true,
+            nanosec: false,
+            timeout: 10,
+            verbose: false,
+            seed: 0,
+            vars: Context::new(),
+            headers: HashMap::new(),
+            environment: None,
+            on_error: HarrawFlow::Continue,
+        }
     }
 }
 
+/// Overlays the selected `environments` entry on top of the plan settings.
+/// Its `vars` and `headers` are merged into the plan ones, anything else replaces them.
+fn hrw_apply_environment(config_doc: &Yaml, environment: Option<&str>) -> Yaml {
+    let mut settings = config_doc.as_hash().cloned().unwrap_or_default();
+
+    if let Some(name) = environment {
+        let environments = config_doc["environments"].as_hash().unwrap_or_else(|| panic!("Unknown environment `{}`, the benchmark has no environments", name));
+        let available: Vec<&str> = environments.keys().filter_map(|key| key.as_str()).collect();
+        let overlay = environments.get(&Yaml::String(name.to_string())).unwrap_or_else(|| panic!("Unknown environment `{}`, available: {}", name, available.join(", ")));
+
+        for (key, value) in overlay.as_hash().unwrap_or_else(|| panic!("Environment `{}` needs to be a hash", name)) {
+            let setting = key.as_str().unwrap_or_default();
+
+            if !ENVIRONMENT_SETTINGS.contains(&setting) {
+                panic!("Unknown environment setting `{}`, use one of {}", setting, ENVIRONMENT_SETTINGS.join(", "));
+            }
+
+            match (setting, settings.get_mut(key), value) {
+                ("vars" | "headers", Some(Yaml::Hash(base)), Yaml::Hash(entries)) => {
+                    for (entry_key, entry_value) in entries {
+                        base.insert(entry_key.clone(), entry_value.clone());
+                    }
+                }
+                _ => {
+                    settings.insert(key.clone(), value.clone());
+                }
+            }
+        }
+    }
+    Yaml::Hash(settings)
+}
+
+fn hrw_read_headers_section(doc: &Yaml) -> HashMap<String, String> {
+    let mut headers = HashMap::new();
+
+    if let Some(hash) = doc.as_hash() {
+        for (key, val) in hash.iter() {
+            match (key.as_str(), val.as_str()) {
+                (Some(key), Some(val)) => headers.insert(key.to_string(), val.to_string()),
+                _ => panic!("Headers must be strings!!"),
+            };
+        }
+    }
+    headers
+}
+
+/// Reads the `--vars-file` and then the `--var key=value` overrides, so the latter win.
+pub fn hrw_read_overrides(vars_file: Option<&str>, vars: &[&str]) -> Context {
+    let mut overrides = Context::new();
+
+    if let Some(path) = vars_file {
+        let content = reader::hrw_read_file(path);
+
+        if path.ends_with(".json") {
+            match serde_json::from_str(&content) {
+                Ok(Value::Object(map)) => overrides.extend(map),
+                Ok(_) => panic!("Vars file {} needs to be a hash", path),
+                Err(e) => panic!("couldn't parse {}: {}", path, e),
+            }
+        } else {
+            let docs = YamlLoader::load_from_str(&content).unwrap_or_else(|e| panic!("couldn't parse {}: {}", path, e));
+            if let Some(doc) = docs.first() {
+                overrides.extend(hrw_read_vars_section(doc, path));
+            }
+        }
+    }
+
+    for var in vars {
+        let (key, value) = var.split_once('=').unwrap_or_else(|| panic!("Invalid --var `{}`, expected key=value", var));
+        overrides.insert(key.trim().to_string(), json!(value));
+    }
+    overrides
+}
+
+fn hrw_read_vars_section(doc: &Yaml, name: &str) -> Context {
+    match doc {
+        Yaml::BadValue | Yaml::Null => Context::new(),
+        Yaml::Hash(_) => match hrw_yaml_to_json(doc.clone()) {
+            Value::Object(map) => map,
+            _ => unreachable!(),
+        },
+        _ => panic!("{} needs to be a hash", name),
+    }
+}
 
 fn hrw_read_str_configuration(config_doc: &Yaml, interpolator: &interpolator::HarrawInterpolator, name: &str, default: &str) -> String {
     match config_doc[name].as_str() {
@@ -85,4 +210,92 @@ fn hrw_read_i64_configuration(config_doc: &Yaml, interpolator: &interpolator::Ha
           default
         }
       }
-}   
\ No newline at end of file
+}
+
+/// The plan `seed`, if it sets one. Runs without one pick a random seed that gets printed so they can be replayed.
+fn hrw_read_seed_configuration(config_doc: &Yaml, interpolator: &interpolator::HarrawInterpolator) -> Option<u64> {
+    let node = &config_doc["seed"];
+
+    let value = match node {
+        Yaml::BadValue | Yaml::Null => return None,
+        Yaml::Integer(value) => value.to_string(),
+        Yaml::String(key) => interpolator.hrw_resolve(key, false),
+        _ => panic!("Invalid seed {:?}, it needs to be a positive integer", node),
+    };
+
+    match value.parse::<u64>() {
+        Ok(seed) => Some(seed),
+        Err(_) => panic!("Invalid seed `{}`, it needs to be a positive integer", value),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hrw_overrides_take_precedence() {
+        let overrides = hrw_read_overrides(Some("./fixtures/vars.yml"), &["user=mary", "token=a=b"]);
+        assert_eq!(overrides.get("user"), Some(&json!("mary")));
+        assert_eq!(overrides.get("token"), Some(&json!("a=b")));
+        assert_eq!(overrides.get("page_size"), Some(&json!(20)));
+
+        let config = HarrawConfig::new("./fixtures/vars_benchmark.yml", false, false, true, false, 10, false, None, &overrides, None);
+        assert_eq!(config.base, "http://mary.localhost:9000");
+        assert_eq!(config.vars.get("user"), Some(&json!("mary")));
+        assert_eq!(config.vars.get("page_size"), Some(&json!(20)));
+        assert_eq!(config.vars.get("locale"), Some(&json!("en")));
+    }
+
+    #[test]
+    fn hrw_environment_overrides_the_plan() {
+        let config = HarrawConfig::new("./fixtures/vars_benchmark.yml", false, false, true, false, 10, false, None, &Context::new(), Some("staging"));
+        assert_eq!(config.base, "http://staging.localhost:9000");
+        assert_eq!(config.iterations, 50);
+        assert_eq!(config.concurrency, 50);
+        assert_eq!(config.vars.get("page_size"), Some(&json!(100)));
+        assert_eq!(config.vars.get("locale"), Some(&json!("en")));
+        assert_eq!(config.headers.get("X-Env").map(String::as_str), Some("staging"));
+        assert_eq!(config.headers.get("Accept").map(String::as_str), Some("application/json"));
+        assert_eq!(config.environment.as_deref(), Some("staging"));
+    }
+
+    #[test]
+    #[should_panic(expected = "Unknown environment `prod`, available: local, staging")]
+    fn hrw_unknown_environment() {
+        HarrawConfig::new("./fixtures/vars_benchmark.yml", false, false, true, false, 10, false, None, &Context::new(), Some("prod"));
+    }
+
+    fn hrw_seed(text: &str) -> Option<u64> {
+        let docs = YamlLoader::load_from_str(text).unwrap();
+        let vars = Context::from_iter([("seed".to_string(), json!("7"))]);
+        hrw_read_seed_configuration(&docs[0], &interpolator::HarrawInterpolator::new(&vars))
+    }
+
+    #[test]
+    fn hrw_reads_the_plan_seed() {
+        assert_eq!(hrw_seed("---\nbase: foo"), None);
+        assert_eq!(hrw_seed("---\nseed: 0"), Some(0));
+        assert_eq!(hrw_seed("---\nseed: 42"), Some(42));
+        assert_eq!(hrw_seed("---\nseed: '{{ seed }}'"), Some(7));
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid seed `-1`, it needs to be a positive integer")]
+    fn hrw_rejects_negative_seeds() {
+        hrw_seed("---\nseed: -1");
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid seed `abc`, it needs to be a positive integer")]
+    fn hrw_rejects_non_integer_seeds() {
+        hrw_seed("---\nseed: abc");
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid --var `user`, expected key=value")]
+    fn hrw_invalid_var() {
+        hrw_read_overrides(None, &["user"]);
+    }
+}
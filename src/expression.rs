@@ -0,0 +1,628 @@
+use std::fmt;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use serde_json::{json, Value};
+
+use crate::functions;
+use crate::interpolator::hrw_value_to_string;
+
+/// Everything but the RFC 3986 unreserved characters gets escaped by `urlencode`.
+const URL_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC.remove(b'-').remove(b'_').remove(b'.').remove(b'~');
+
+// Longest symbols first, so `==` isn't read as two `=`
+static SYMBOLS: [&str; 22] = ["==", "!=", "<=", ">=", "&&", "||", "<", ">", "!", "|", "+", "-", "*", "/", "%", "~", "(", ")", "[", "]", ",", "."];
+
+#[derive(Debug, PartialEq)]
+pub enum HarrawExpressionError {
+  Syntax(String),
+  Undefined(String),
+  Invalid(String),
+}
+
+impl fmt::Display for HarrawExpressionError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      HarrawExpressionError::Syntax(message) => write!(f, "Invalid expression: {message}"),
+      HarrawExpressionError::Undefined(name) => write!(f, "Unknown '{name}' variable!"),
+      HarrawExpressionError::Invalid(message) => write!(f, "{message}"),
+    }
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HarrawBinaryOperator {
+  Add,
+  Subtract,
+  Multiply,
+  Divide,
+  Remainder,
+  Concat,
+  Equals,
+  NotEquals,
+  Lt,
+  Lte,
+  Gt,
+  Gte,
+  In,
+  NotIn,
+  And,
+  Or,
+}
+
+impl fmt::Display for HarrawBinaryOperator {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let symbol = match self {
+      HarrawBinaryOperator::Add => "+",
+      HarrawBinaryOperator::Subtract => "-",
+      HarrawBinaryOperator::Multiply => "*",
+      HarrawBinaryOperator::Divide => "/",
+      HarrawBinaryOperator::Remainder => "%",
+      HarrawBinaryOperator::Concat => "~",
+      HarrawBinaryOperator::Equals => "==",
+      HarrawBinaryOperator::NotEquals => "!=",
+      HarrawBinaryOperator::Lt => "<",
+      HarrawBinaryOperator::Lte => "<=",
+      HarrawBinaryOperator::Gt => ">",
+      HarrawBinaryOperator::Gte => ">=",
+      HarrawBinaryOperator::In => "in",
+      HarrawBinaryOperator::NotIn => "not in",
+      HarrawBinaryOperator::And => "and",
+      HarrawBinaryOperator::Or => "or",
+    };
+    write!(f, "{symbol}")
+  }
+}
+
+/// The body of a `{{ ... }}` interpolation: literals, context paths, function calls, arithmetic,
+/// comparisons, boolean logic and filter pipes.
+#[derive(Clone, Debug, PartialEq)]
+pub enum HarrawExpression {
+  Literal(Value),
+  List(Vec<HarrawExpression>),
+  Path(String),
+  Call(String, Vec<HarrawExpression>),
+  Negate(Box<HarrawExpression>),
+  Not(Box<HarrawExpression>),
+  Binary(HarrawBinaryOperator, Box<HarrawExpression>, Box<HarrawExpression>),
+  Filter(Box<HarrawExpression>, String, Vec<HarrawExpression>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum HarrawToken {
+  Number(Value),
+  Str(String),
+  Ident(String),
+  Symbol(&'static str),
+}
+
+impl fmt::Display for HarrawToken {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      HarrawToken::Number(number) => write!(f, "`{number}`"),
+      HarrawToken::Str(string) => write!(f, "`'{string}'`"),
+      HarrawToken::Ident(ident) => write!(f, "`{ident}`"),
+      HarrawToken::Symbol(symbol) => write!(f, "`{symbol}`"),
+    }
+  }
+}
+
+fn hrw_tokenize(source: &str) -> Result<Vec<HarrawToken>, HarrawExpressionError> {
+  let chars: Vec<char> = source.chars().collect();
+  let mut tokens = Vec::new();
+  let mut i = 0;
+
+  while i < chars.len() {
+    let c = chars[i];
+
+    if c.is_whitespace() {
+      i += 1;
+    } else if c.is_ascii_digit() {
+      let start = i;
+      while i < chars.len() && chars[i].is_ascii_digit() {
+        i += 1;
+      }
+      // `items.0.1` is a path, not the number 0.1
+      let after_dot = tokens.last() == Some(&HarrawToken::Symbol("."));
+      if !after_dot && i + 1 < chars.len() && chars[i] == '.' && chars[i + 1].is_ascii_digit() {
+        i += 1;
+        while i < chars.len() && chars[i].is_ascii_digit() {
+          i += 1;
+        }
+      }
+      let text: String = chars[start..i].iter().collect();
+      let number = serde_json::from_str(&text).map_err(|_| HarrawExpressionError::Syntax(format!("invalid number `{text}`")))?;
+      tokens.push(HarrawToken::Number(number));
+    } else if c == '\'' || c == '"' {
+      let mut string = String::new();
+      i += 1;
+      loop {
+        match chars.get(i) {
+          None => return Err(HarrawExpressionError::Syntax("unterminated string".to_string())),
+          Some(&quote) if quote == c => break,
+          Some('\\') => {
+            i += 1;
+            match chars.get(i) {
+              Some('n') => string.push('\n'),
+              Some('t') => string.push('\t'),
+              Some(&escaped) => string.push(escaped),
+              None => return Err(HarrawExpressionError::Syntax("unterminated string".to_string())),
+            }
+          }
+          Some(&other) => string.push(other),
+        }
+        i += 1;
+      }
+      i += 1;
+      tokens.push(HarrawToken::Str(string));
+    } else if c.is_alphabetic() || c == '_' || c == '$' {
+      let start = i;
+      while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_' || chars[i] == '$') {
+        i += 1;
+      }
+      tokens.push(HarrawToken::Ident(chars[start..i].iter().collect()));
+    } else {
+      let rest: String = chars[i..].iter().collect();
+      let symbol = SYMBOLS.iter().find(|symbol| rest.starts_with(*symbol)).ok_or_else(|| HarrawExpressionError::Syntax(format!("unexpected `{c}`")))?;
+      i += symbol.chars().count();
+      tokens.push(HarrawToken::Symbol(symbol));
+    }
+  }
+  Ok(tokens)
+}
+
+struct HarrawParser {
+  tokens: Vec<HarrawToken>,
+  position: usize,
+}
+
+impl HarrawParser {
+  fn hrw_peek(&self) -> Option<&HarrawToken> {
+    self.tokens.get(self.position)
+  }
+
+  fn hrw_next(&mut self) -> Option<HarrawToken> {
+    let token = self.tokens.get(self.position).cloned();
+    self.position += 1;
+    token
+  }
+
+  /// Consumes the next token if it is the given symbol or keyword, like `and`.
+  fn hrw_accept(&mut self, symbol: &str) -> bool {
+    if matches!(self.hrw_peek(), Some(HarrawToken::Symbol(s)) if *s == symbol) || matches!(self.hrw_peek(), Some(HarrawToken::Ident(s)) if s == symbol) {
+      self.position += 1;
+      true
+    } else {
+      false
+    }
+  }
+
+  fn hrw_expect(&mut self, symbol: &str) -> Result<(), HarrawExpressionError> {
+    if self.hrw_accept(symbol) {
+      Ok(())
+    } else {
+      Err(self.hrw_unexpected())
+    }
+  }
+
+  fn hrw_unexpected(&self) -> HarrawExpressionError {
+    match self.hrw_peek() {
+      Some(token) => HarrawExpressionError::Syntax(format!("unexpected {token}")),
+      None => HarrawExpressionError::Syntax("unexpected end of expression".to_string()),
+    }
+  }
+
+  fn hrw_binary(&mut self, operators: &[(&str, HarrawBinaryOperator)], operand: fn(&mut Self) -> Result<HarrawExpression, HarrawExpressionError>) -> Result<HarrawExpression, HarrawExpressionError> {
+    let mut left = operand(self)?;
+
+    'outer: loop {
+      for (symbol, operator) in operators.iter() {
+        if self.hrw_accept(symbol) {
+          let right = operand(self)?;
+          left = HarrawExpression::Binary(*operator, Box::new(left), Box::new(right));
+          continue 'outer;
+        }
+      }
+      return Ok(left);
+    }
+  }
+
+  fn hrw_expression(&mut self) -> Result<HarrawExpression, HarrawExpressionError> {
+    self.hrw_binary(&[("or", HarrawBinaryOperator::Or), ("||", HarrawBinaryOperator::Or)], Self::hrw_and)
+  }
+
+  fn hrw_and(&mut self) -> Result<HarrawExpression, HarrawExpressionError> {
+    self.hrw_binary(&[("and", HarrawBinaryOperator::And), ("&&", HarrawBinaryOperator::And)], Self::hrw_not)
+  }
+
+  fn hrw_not(&mut self) -> Result<HarrawExpression, HarrawExpressionError> {
+    if self.hrw_accept("not") || self.hrw_accept("!") {
+      Ok(HarrawExpression::Not(Box::new(self.hrw_not()?)))
+    } else {
+      self.hrw_comparison()
+    }
+  }
+
+  fn hrw_comparison(&mut self) -> Result<HarrawExpression, HarrawExpressionError> {
+    let operators = [
+      ("==", HarrawBinaryOperator::Equals),
+      ("!=", HarrawBinaryOperator::NotEquals),
+      ("<=", HarrawBinaryOperator::Lte),
+      (">=", HarrawBinaryOperator::Gte),
+      ("<", HarrawBinaryOperator::Lt),
+      (">", HarrawBinaryOperator::Gt),
+      ("in", HarrawBinaryOperator::In),
+    ];
+    let mut left = self.hrw_concat()?;
+
+    loop {
+      let operator = if matches!(self.tokens.get(self.position..self.position + 2), Some([HarrawToken::Ident(not), HarrawToken::Ident(is_in)]) if not == "not" && is_in == "in") {
+        self.position += 2;
+        HarrawBinaryOperator::NotIn
+      } else if let Some((_, operator)) = operators.iter().find(|(symbol, _)| self.hrw_accept(symbol)) {
+        *operator
+      } else {
+        return Ok(left);
+      };
+      let right = self.hrw_concat()?;
+      left = HarrawExpression::Binary(operator, Box::new(left), Box::new(right));
+    }
+  }
+
+  fn hrw_concat(&mut self) -> Result<HarrawExpression, HarrawExpressionError> {
+    self.hrw_binary(&[("~", HarrawBinaryOperator::Concat)], Self::hrw_additive)
+  }
+
+  fn hrw_additive(&mut self) -> Result<HarrawExpression, HarrawExpressionError> {
+    self.hrw_binary(&[("+", HarrawBinaryOperator::Add), ("-", HarrawBinaryOperator::Subtract)], Self::hrw_multiplicative)
+  }
+
+  fn hrw_multiplicative(&mut self) -> Result<HarrawExpression, HarrawExpressionError> {
+    self.hrw_binary(&[("*", HarrawBinaryOperator::Multiply), ("/", HarrawBinaryOperator::Divide), ("%", HarrawBinaryOperator::Remainder)], Self::hrw_unary)
+  }
+
+  fn hrw_unary(&mut self) -> Result<HarrawExpression, HarrawExpressionError> {
+    if self.hrw_accept("-") {
+      Ok(HarrawExpression::Negate(Box::new(self.hrw_unary()?)))
+    } else {
+      self.hrw_filtered()
+    }
+  }
+
+  fn hrw_filtered(&mut self) -> Result<HarrawExpression, HarrawExpressionError> {
+    let mut expression = self.hrw_primary()?;
+
+    while self.hrw_accept("|") {
+      let name = match self.hrw_next() {
+        Some(HarrawToken::Ident(name)) => name,
+        _ => return Err(HarrawExpressionError::Syntax("expected a filter name after `|`".to_string())),
+      };
+      let args = if self.hrw_accept("(") {
+        self.hrw_list(")")?
+      } else {
+        Vec::new()
+      };
+      expression = HarrawExpression::Filter(Box::new(expression), name, args);
+    }
+    Ok(expression)
+  }
+
+  /// Comma separated expressions up to the closing symbol.
+  fn hrw_list(&mut self, close: &str) -> Result<Vec<HarrawExpression>, HarrawExpressionError> {
+    let mut items = Vec::new();
+
+    if self.hrw_accept(close) {
+      return Ok(items);
+    }
+    loop {
+      items.push(self.hrw_expression()?);
+      if self.hrw_accept(close) {
+        return Ok(items);
+      }
+      self.hrw_expect(",")?;
+    }
+  }
+
+  fn hrw_primary(&mut self) -> Result<HarrawExpression, HarrawExpressionError> {
+    match self.hrw_next() {
+      Some(HarrawToken::Number(number)) => Ok(HarrawExpression::Literal(number)),
+      Some(HarrawToken::Str(string)) => Ok(HarrawExpression::Literal(Value::String(string))),
+      Some(HarrawToken::Symbol("(")) => {
+        let expression = self.hrw_expression()?;
+        self.hrw_expect(")")?;
+        Ok(expression)
+      }
+      Some(HarrawToken::Symbol("[")) => Ok(HarrawExpression::List(self.hrw_list("]")?)),
+      Some(HarrawToken::Ident(ident)) => match ident.as_str() {
+        "true" => Ok(HarrawExpression::Literal(json!(true))),
+        "false" => Ok(HarrawExpression::Literal(json!(false))),
+        "null" => Ok(HarrawExpression::Literal(Value::Null)),
+        _ if self.hrw_accept("(") => Ok(HarrawExpression::Call(ident, self.hrw_list(")")?)),
+        _ => self.hrw_path(ident),
+      },
+      _ => {
+        self.position -= 1;
+        Err(self.hrw_unexpected())
+      }
+    }
+  }
+
+  fn hrw_path(&mut self, mut path: String) -> Result<HarrawExpression, HarrawExpressionError> {
+    loop {
+      if self.hrw_accept(".") {
+        match self.hrw_next() {
+          Some(HarrawToken::Ident(segment)) => path.push_str(&format!(".{segment}")),
+          Some(HarrawToken::Number(index)) if index.is_u64() => path.push_str(&format!(".{index}")),
+          _ => return Err(HarrawExpressionError::Syntax(format!("invalid path after `{path}`"))),
+        }
+      } else if self.hrw_accept("[") {
+        match self.hrw_next() {
+          Some(HarrawToken::Number(index)) if index.is_u64() => path.push_str(&format!("[{index}]")),
+          Some(HarrawToken::Str(key)) => path.push_str(&format!(".{key}")),
+          _ => return Err(HarrawExpressionError::Syntax(format!("invalid index after `{path}`"))),
+        }
+        self.hrw_expect("]")?;
+      } else {
+        return Ok(HarrawExpression::Path(path));
+      }
+    }
+  }
+}
+
+pub fn hrw_parse(source: &str) -> Result<HarrawExpression, HarrawExpressionError> {
+  let mut parser = HarrawParser {
+    tokens: hrw_tokenize(source)?,
+    position: 0,
+  };
+  let expression = parser.hrw_expression()?;
+
+  if parser.hrw_peek().is_some() {
+    return Err(parser.hrw_unexpected());
+  }
+  Ok(expression)
+}
+
+impl HarrawExpression {
+  /// Evaluates the expression, resolving paths through `lookup`.
+  pub fn hrw_evaluate(&self, lookup: &dyn Fn(&str) -> Option<Value>) -> Result<Value, HarrawExpressionError> {
+    match self {
+      HarrawExpression::Literal(value) => Ok(value.clone()),
+      HarrawExpression::List(items) => items.iter().map(|item| item.hrw_evaluate(lookup)).collect::<Result<Vec<_>, _>>().map(Value::Array),
+      HarrawExpression::Path(path) => lookup(path).ok_or_else(|| HarrawExpressionError::Undefined(path.to_owned())),
+      HarrawExpression::Call(name, args) => {
+        let args = args.iter().map(|arg| arg.hrw_evaluate(lookup)).collect::<Result<Vec<_>, _>>()?;
+        functions::hrw_call(name, &args)
+      }
+      HarrawExpression::Negate(operand) => {
+        let value = operand.hrw_evaluate(lookup)?;
+        hrw_number(&value).map(|n| hrw_number_value(-n)).ok_or_else(|| HarrawExpressionError::Invalid(format!("cannot negate `{}`", hrw_value_to_string(&value))))
+      }
+      HarrawExpression::Not(operand) => Ok(json!(!hrw_truthy(&operand.hrw_evaluate(lookup)?))),
+      HarrawExpression::Binary(HarrawBinaryOperator::And, left, right) => Ok(json!(hrw_truthy(&left.hrw_evaluate(lookup)?) && hrw_truthy(&right.hrw_evaluate(lookup)?))),
+      HarrawExpression::Binary(HarrawBinaryOperator::Or, left, right) => Ok(json!(hrw_truthy(&left.hrw_evaluate(lookup)?) || hrw_truthy(&right.hrw_evaluate(lookup)?))),
+      HarrawExpression::Binary(operator, left, right) => hrw_apply_operator(*operator, left.hrw_evaluate(lookup)?, right.hrw_evaluate(lookup)?),
+      HarrawExpression::Filter(input, name, args) => {
+        let input = input.hrw_evaluate(lookup);
+
+        // `default` is the only filter that gets to see undefined values
+        if name == "default" {
+          return match input {
+            Err(HarrawExpressionError::Undefined(_)) | Ok(Value::Null) => args.first().map_or(Ok(json!("")), |fallback| fallback.hrw_evaluate(lookup)),
+            other => other,
+          };
+        }
+
+        let args = args.iter().map(|arg| arg.hrw_evaluate(lookup)).collect::<Result<Vec<_>, _>>()?;
+        hrw_apply_filter(name, input?, &args)
+      }
+    }
+  }
+}
+
+/// Null, false, zero and empty strings, lists and objects are false.
+pub fn hrw_truthy(value: &Value) -> bool {
+  match value {
+    Value::Null => false,
+    Value::Bool(b) => *b,
+    Value::Number(number) => number.as_f64() != Some(0.0),
+    Value::String(string) => !string.is_empty(),
+    Value::Array(items) => !items.is_empty(),
+    Value::Object(map) => !map.is_empty(),
+  }
+}
+
+/// Numbers compare with numeric strings, so `{{ status }} == '200'` holds,
+/// and scalars compare by their rendered text.
+fn hrw_loose_eq(left: &Value, right: &Value) -> bool {
+  if left.is_number() || right.is_number() {
+    if let (Some(l), Some(r)) = (hrw_number(left), hrw_number(right)) {
+      return l == r;
+    }
+  }
+
+  match (left, right) {
+    (Value::Array(_) | Value::Object(_), _) | (_, Value::Array(_) | Value::Object(_)) => left == right,
+    _ => left.is_null() == right.is_null() && hrw_value_to_string(left) == hrw_value_to_string(right),
+  }
+}
+
+fn hrw_compare(operator: HarrawBinaryOperator, left: &Value, right: &Value) -> Result<bool, HarrawExpressionError> {
+  let ordering = match (hrw_number(left), hrw_number(right)) {
+    (Some(l), Some(r)) if left.is_number() || right.is_number() => l.partial_cmp(&r),
+    _ => match (left, right) {
+      (Value::String(l), Value::String(r)) => Some(l.cmp(r)),
+      _ => None,
+    },
+  };
+
+  let ordering = ordering.ok_or_else(|| HarrawExpressionError::Invalid(format!("cannot compare `{}` {} `{}`", hrw_value_to_string(left), operator, hrw_value_to_string(right))))?;
+
+  Ok(match operator {
+    HarrawBinaryOperator::Lt => ordering.is_lt(),
+    HarrawBinaryOperator::Lte => ordering.is_le(),
+    HarrawBinaryOperator::Gt => ordering.is_gt(),
+    _ => ordering.is_ge(),
+  })
+}
+
+fn hrw_contains(container: &Value, value: &Value) -> Result<bool, HarrawExpressionError> {
+  match container {
+    Value::Array(items) => Ok(items.iter().any(|item| hrw_loose_eq(item, value))),
+    Value::Object(map) => Ok(map.contains_key(&hrw_value_to_string(value))),
+    Value::String(string) => Ok(string.contains(&hrw_value_to_string(value))),
+    _ => Err(HarrawExpressionError::Invalid(format!("cannot look for `{}` in `{}`", hrw_value_to_string(value), hrw_value_to_string(container)))),
+  }
+}
+
+/// Numbers and numeric strings take part in arithmetic.
+pub fn hrw_number(value: &Value) -> Option<f64> {
+  match value {
+    Value::Number(number) => number.as_f64(),
+    Value::String(string) => string.trim().parse().ok(),
+    _ => None,
+  }
+}
+
+/// Keeps whole results as integers so `{{ page + 1 }}` renders `2`, not `2.0`.
+fn hrw_number_value(number: f64) -> Value {
+  if number.fract() == 0.0 && number.abs() < 9e15 {
+    json!(number as i64)
+  } else {
+    json!(number)
+  }
+}
+
+fn hrw_apply_operator(operator: HarrawBinaryOperator, left: Value, right: Value) -> Result<Value, HarrawExpressionError> {
+  match operator {
+    HarrawBinaryOperator::Concat => return Ok(json!(format!("{}{}", hrw_value_to_string(&left), hrw_value_to_string(&right)))),
+    HarrawBinaryOperator::Equals => return Ok(json!(hrw_loose_eq(&left, &right))),
+    HarrawBinaryOperator::NotEquals => return Ok(json!(!hrw_loose_eq(&left, &right))),
+    HarrawBinaryOperator::Lt | HarrawBinaryOperator::Lte | HarrawBinaryOperator::Gt | HarrawBinaryOperator::Gte => return hrw_compare(operator, &left, &right).map(|result| json!(result)),
+    HarrawBinaryOperator::In => return hrw_contains(&right, &left).map(|result| json!(result)),
+    HarrawBinaryOperator::NotIn => return hrw_contains(&right, &left).map(|result| json!(!result)),
+    _ => {}
+  }
+
+  match (hrw_number(&left), hrw_number(&right)) {
+    (Some(l), Some(r)) => match operator {
+      HarrawBinaryOperator::Add => Ok(hrw_number_value(l + r)),
+      HarrawBinaryOperator::Subtract => Ok(hrw_number_value(l - r)),
+      HarrawBinaryOperator::Multiply => Ok(hrw_number_value(l * r)),
+      HarrawBinaryOperator::Divide | HarrawBinaryOperator::Remainder if r == 0.0 => Err(HarrawExpressionError::Invalid("division by zero".to_string())),
+      HarrawBinaryOperator::Divide => Ok(hrw_number_value(l / r)),
+      _ => Ok(hrw_number_value(l % r)),
+    },
+    _ => match (operator, left, right) {
+      (HarrawBinaryOperator::Add, Value::Array(mut l), Value::Array(r)) => {
+        l.extend(r);
+        Ok(Value::Array(l))
+      }
+      (HarrawBinaryOperator::Add, l, r) => Ok(json!(format!("{}{}", hrw_value_to_string(&l), hrw_value_to_string(&r)))),
+      (operator, l, r) => Err(HarrawExpressionError::Invalid(format!("cannot apply `{}` to `{}` and `{}`", operator, hrw_value_to_string(&l), hrw_value_to_string(&r)))),
+    },
+  }
+}
+
+fn hrw_apply_filter(name: &str, value: Value, args: &[Value]) -> Result<Value, HarrawExpressionError> {
+  if !args.is_empty() {
+    return Err(HarrawExpressionError::Invalid(format!("Filter `{name}` takes no arguments")));
+  }
+
+  let text = hrw_value_to_string(&value);
+
+  match name {
+    "upper" => Ok(json!(text.to_uppercase())),
+    "lower" => Ok(json!(text.to_lowercase())),
+    "trim" => Ok(json!(text.trim())),
+    "urlencode" => Ok(json!(utf8_percent_encode(&text, URL_ENCODE_SET).to_string())),
+    "base64" => Ok(json!(STANDARD.encode(text))),
+    "base64decode" => {
+      let bytes = STANDARD.decode(text.trim()).map_err(|e| HarrawExpressionError::Invalid(format!("`{text}` is not base64: {e}")))?;
+      String::from_utf8(bytes).map(Value::String).map_err(|_| HarrawExpressionError::Invalid(format!("`{text}` does not decode to UTF-8")))
+    }
+    "json" => Ok(json!(serde_json::to_string(&value).unwrap())),
+    "length" => match value {
+      Value::String(string) => Ok(json!(string.chars().count())),
+      Value::Array(items) => Ok(json!(items.len())),
+      Value::Object(map) => Ok(json!(map.len())),
+      _ => Err(HarrawExpressionError::Invalid(format!("`{text}` has no length"))),
+    },
+    _ => Err(HarrawExpressionError::Invalid(format!("Unknown filter `{name}`"))),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn hrw_eval(source: &str) -> Result<Value, HarrawExpressionError> {
+    let context = json!({"user": {"name": "Mary Jane", "id": 12, "roles": ["admin", "dev"]}, "page": "2", "token": "Zm9vOmJhcg=="});
+    hrw_parse(source)?.hrw_evaluate(&|path| context.pointer(&crate::interpolator::hrw_json_pointer(path)).cloned())
+  }
+
+  #[test]
+  fn hrw_applies_filters() {
+    assert_eq!(hrw_eval("user.name | urlencode"), Ok(json!("Mary%20Jane")));
+    assert_eq!(hrw_eval("user.name | upper"), Ok(json!("MARY JANE")));
+    assert_eq!(hrw_eval("'  Mary ' | trim | lower"), Ok(json!("mary")));
+    assert_eq!(hrw_eval("'foo:bar' | base64"), Ok(json!("Zm9vOmJhcg==")));
+    assert_eq!(hrw_eval("token | base64decode"), Ok(json!("foo:bar")));
+    assert_eq!(hrw_eval("user.roles | json"), Ok(json!("[\"admin\",\"dev\"]")));
+    assert_eq!(hrw_eval("user.roles | length"), Ok(json!(2)));
+    assert_eq!(hrw_eval("user.name | length"), Ok(json!(9)));
+  }
+
+  #[test]
+  fn hrw_default_catches_undefined_values() {
+    assert_eq!(hrw_eval("user.email | default('none')"), Ok(json!("none")));
+    assert_eq!(hrw_eval("user.id | default(0)"), Ok(json!(12)));
+    assert_eq!(hrw_eval("user.email | upper"), Err(HarrawExpressionError::Undefined("user.email".to_string())));
+  }
+
+  #[test]
+  fn hrw_computes_arithmetic() {
+    assert_eq!(hrw_eval("page + 1"), Ok(json!(3)));
+    assert_eq!(hrw_eval("user.id * (page - 1) + 1"), Ok(json!(13)));
+    assert_eq!(hrw_eval("-user.id / 8"), Ok(json!(-1.5)));
+    assert_eq!(hrw_eval("7 % 4"), Ok(json!(3)));
+    assert_eq!(hrw_eval("user.roles[1]"), Ok(json!("dev")));
+    assert!(matches!(hrw_eval("1 / 0"), Err(HarrawExpressionError::Invalid(_))));
+  }
+
+  #[test]
+  fn hrw_concatenates_strings() {
+    assert_eq!(hrw_eval("'user-' ~ user.id"), Ok(json!("user-12")));
+    assert_eq!(hrw_eval("user.name + '!'"), Ok(json!("Mary Jane!")));
+    assert_eq!(hrw_eval("'id-' ~ user.id + 1"), Ok(json!("id-13")));
+    assert_eq!(hrw_eval("[1] + [2, 3]"), Ok(json!([1, 2, 3])));
+  }
+
+  #[test]
+  fn hrw_calls_functions() {
+    assert_eq!(hrw_eval("random_int(user.id, 12) + 1"), Ok(json!(13)));
+    assert!(["admin", "dev"].contains(&hrw_eval("random_choice(user.roles)").unwrap().as_str().unwrap()));
+    assert_eq!(hrw_eval("random_string(4) | length"), Ok(json!(4)));
+  }
+
+  #[test]
+  fn hrw_compares_values() {
+    assert_eq!(hrw_eval("user.id == 12"), Ok(json!(true)));
+    assert_eq!(hrw_eval("user.id == '12'"), Ok(json!(true)));
+    assert_eq!(hrw_eval("page > 1 and page <= 2"), Ok(json!(true)));
+    assert_eq!(hrw_eval("user.name != 'Mary Jane' || user.id >= 13"), Ok(json!(false)));
+    assert_eq!(hrw_eval("'admin' in user.roles and 'ops' not in user.roles"), Ok(json!(true)));
+    assert_eq!(hrw_eval("not user.email | default(false)"), Ok(json!(true)));
+    assert_eq!(hrw_eval("!(user.id + 1 == 13)"), Ok(json!(false)));
+    assert_eq!(hrw_eval("'Jane' in user.name"), Ok(json!(true)));
+    assert!(matches!(hrw_eval("user.roles < 3"), Err(HarrawExpressionError::Invalid(_))));
+  }
+
+  #[test]
+  fn hrw_rejects_invalid_syntax() {
+    assert!(matches!(hrw_parse("5digitzip"), Err(HarrawExpressionError::Syntax(_))));
+    assert!(matches!(hrw_parse("user.name |"), Err(HarrawExpressionError::Syntax(_))));
+    assert!(matches!(hrw_parse("'open"), Err(HarrawExpressionError::Syntax(_))));
+    assert!(matches!(hrw_eval("user | shout"), Err(HarrawExpressionError::Invalid(_))));
+  }
+}
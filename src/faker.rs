@@ -0,0 +1,100 @@
+use fake::faker::address::raw::{BuildingNumber, CityName, CountryName, StateName, StreetName, ZipCode};
+use fake::faker::company::raw::CompanyName;
+use fake::faker::creditcard::raw::CreditCardNumber;
+use fake::faker::internet::raw::{IPv4, IPv6, Password, SafeEmail, UserAgent, Username};
+use fake::faker::job::raw::Title;
+use fake::faker::lorem::raw::{Paragraph, Sentence, Word};
+use fake::faker::name::raw::{FirstName, LastName, Name};
+use fake::faker::phone_number::raw::{CellNumber, PhoneNumber};
+use fake::locales::{Data, AR_SA, EN, FR_FR, JA_JP, PT_BR, ZH_CN, ZH_TW};
+use fake::Fake;
+use rand::Rng;
+use serde_json::{json, Value};
+
+use crate::expression::HarrawExpressionError;
+
+pub static LOCALES: [&str; 7] = ["en", "fr_fr", "pt_br", "ja_jp", "zh_cn", "zh_tw", "ar_sa"];
+
+/// Generates a fake value of the given kind, like `faker('email')` or `faker('name', 'fr_fr')`.
+pub fn hrw_fake<R: Rng>(kind: &str, locale: &str, rng: &mut R) -> Result<Value, HarrawExpressionError> {
+  let fake = match locale.to_lowercase().replace('-', "_").as_str() {
+    "en" => hrw_fake_in(kind, EN, rng),
+    "fr_fr" => hrw_fake_in(kind, FR_FR, rng),
+    "pt_br" => hrw_fake_in(kind, PT_BR, rng),
+    "ja_jp" => hrw_fake_in(kind, JA_JP, rng),
+    "zh_cn" => hrw_fake_in(kind, ZH_CN, rng),
+    "zh_tw" => hrw_fake_in(kind, ZH_TW, rng),
+    "ar_sa" => hrw_fake_in(kind, AR_SA, rng),
+    _ => return Err(HarrawExpressionError::Invalid(format!("Unknown faker locale `{}`, use one of {}", locale, LOCALES.join(", ")))),
+  };
+
+  fake.map(|value| json!(value)).ok_or_else(|| HarrawExpressionError::Invalid(format!("Unknown faker `{kind}`")))
+}
+
+fn hrw_fake_in<L: Data + Copy, R: Rng>(kind: &str, locale: L, rng: &mut R) -> Option<String> {
+  let value = match kind {
+    "first_name" => FirstName(locale).fake_with_rng(rng),
+    "last_name" => LastName(locale).fake_with_rng(rng),
+    "name" => Name(locale).fake_with_rng(rng),
+    "email" => SafeEmail(locale).fake_with_rng(rng),
+    "username" => Username(locale).fake_with_rng(rng),
+    "password" => Password(locale, 12..20).fake_with_rng(rng),
+    "phone" => PhoneNumber(locale).fake_with_rng(rng),
+    "cell_phone" => CellNumber(locale).fake_with_rng(rng),
+    "street" => StreetName(locale).fake_with_rng(rng),
+    "city" => CityName(locale).fake_with_rng(rng),
+    "zip" => ZipCode(locale).fake_with_rng(rng),
+    "state" => StateName(locale).fake_with_rng(rng),
+    "country" => CountryName(locale).fake_with_rng(rng),
+    "address" => {
+      let number: String = BuildingNumber(locale).fake_with_rng(rng);
+      let street: String = StreetName(locale).fake_with_rng(rng);
+      let city: String = CityName(locale).fake_with_rng(rng);
+      let zip: String = ZipCode(locale).fake_with_rng(rng);
+      format!("{number} {street}, {zip} {city}")
+    }
+    "company" => CompanyName(locale).fake_with_rng(rng),
+    "job_title" => Title(locale).fake_with_rng(rng),
+    "credit_card" => CreditCardNumber(locale).fake_with_rng(rng),
+    "ipv4" => IPv4(locale).fake_with_rng(rng),
+    "ipv6" => IPv6(locale).fake_with_rng(rng),
+    "user_agent" => UserAgent(locale).fake_with_rng(rng),
+    "word" => Word(locale).fake_with_rng(rng),
+    "sentence" => Sentence(locale, 4..10).fake_with_rng(rng),
+    "paragraph" => Paragraph(locale, 3..6).fake_with_rng(rng),
+    _ => return None,
+  };
+  Some(value)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use rand::rngs::StdRng;
+  use rand::SeedableRng;
+
+  #[test]
+  fn hrw_fakes_values() {
+    let mut rng = StdRng::seed_from_u64(1);
+
+    assert!(hrw_fake("email", "en", &mut rng).unwrap().as_str().unwrap().contains('@'));
+    assert_eq!(hrw_fake("ipv4", "en", &mut rng).unwrap().as_str().unwrap().split('.').count(), 4);
+    assert!(!hrw_fake("name", "fr-FR", &mut rng).unwrap().as_str().unwrap().is_empty());
+  }
+
+  #[test]
+  fn hrw_fakes_reproducibly() {
+    let first = hrw_fake("address", "en", &mut StdRng::seed_from_u64(7));
+    let second = hrw_fake("address", "en", &mut StdRng::seed_from_u64(7));
+
+    assert_eq!(first, second);
+  }
+
+  #[test]
+  fn hrw_rejects_unknown_fakers() {
+    let mut rng = StdRng::seed_from_u64(1);
+
+    assert_eq!(hrw_fake("pet", "en", &mut rng), Err(HarrawExpressionError::Invalid("Unknown faker `pet`".to_string())));
+    assert!(matches!(hrw_fake("name", "xx", &mut rng), Err(HarrawExpressionError::Invalid(_))));
+  }
+}
@@ -0,0 +1,221 @@
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use colored::*;
+use rand::seq::SliceRandom;
+use serde_json::Value;
+use yaml_rust::Yaml;
+
+use crate::benchmark::Context;
+use crate::random::hrw_with_rng;
+use crate::reader;
+use crate::service::{hrw_extract_optional, hrw_yaml_to_json};
+
+/// Set by the benchmark in every iteration, a row can't be fed under these names.
+const RESERVED_FIELDS: [&str; 2] = ["base", "iterations"];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum HarrawStrategy {
+  Sequential,
+  Random,
+  Unique,
+}
+
+/// A plan level `data:` source handing one row to every iteration.
+pub struct HarrawFeeder {
+  file: String,
+  rows: Vec<Value>,
+  strategy: HarrawStrategy,
+  recycle: bool,
+  alias: Option<String>,
+  exhausted: AtomicBool,
+}
+
+/// Reads the `data` section of the benchmark, either one source or a list of them.
+pub fn hrw_read_feeders(parent_path: &str, doc: &Yaml) -> Vec<HarrawFeeder> {
+  match doc {
+    Yaml::BadValue | Yaml::Null => Vec::new(),
+    Yaml::Array(sources) => sources.iter().map(|source| HarrawFeeder::new(parent_path, source)).collect(),
+    Yaml::Hash(_) => vec![HarrawFeeder::new(parent_path, doc)],
+    _ => panic!("`data` needs to be a hash or a list of hashes"),
+  }
+}
+
+impl HarrawFeeder {
+  pub fn new(parent_path: &str, source: &Yaml) -> HarrawFeeder {
+    let file = hrw_extract_optional(source, "file").expect("`data` needs a file");
+    let filepath = Path::new(parent_path).with_file_name(&file);
+    let final_path = filepath.to_str().unwrap();
+
+    let format = hrw_extract_optional(source, "format").unwrap_or_else(|| match Path::new(&file).extension().and_then(|e| e.to_str()) {
+      Some("csv") => "csv".to_string(),
+      Some("json") => "json".to_string(),
+      Some("jsonl") | Some("ndjson") => "jsonl".to_string(),
+      _ => "lines".to_string(),
+    });
+
+    let mut rows: Vec<Value> = match format.as_str() {
+      "csv" => {
+        let quote_char = hrw_extract_optional(source, "quote_char").unwrap_or_else(|| "\"".to_string()).bytes().next().unwrap();
+        reader::hrw_read_csv_file_as_yml(final_path, quote_char).into_iter().map(hrw_yaml_to_json).collect()
+      }
+      "json" => reader::hrw_read_json_file(final_path),
+      "jsonl" => reader::hrw_read_jsonl_file(final_path),
+      "lines" => reader::hrw_read_file_as_yml_array(final_path).into_iter().map(hrw_yaml_to_json).collect(),
+      _ => panic!("Unknown data format `{}`, expected csv, json, jsonl or lines", format),
+    };
+
+    let strategy = match hrw_extract_optional(source, "strategy").as_deref() {
+      None | Some("sequential") => HarrawStrategy::Sequential,
+      Some("random") => HarrawStrategy::Random,
+      Some("unique") => HarrawStrategy::Unique,
+      Some(other) => panic!("Unknown data strategy `{}`, expected sequential, random or unique", other),
+    };
+
+    // Unique rows are never handed out twice unless asked to
+    let recycle = match hrw_extract_optional(source, "on_exhausted").as_deref() {
+      None => strategy != HarrawStrategy::Unique,
+      Some("recycle") => true,
+      Some("stop") => false,
+      Some(other) => panic!("Unknown on_exhausted policy `{}`, expected recycle or stop", other),
+    };
+
+    let alias = hrw_extract_optional(source, "as");
+
+    if rows.is_empty() {
+      panic!("Data file {} has no rows", file);
+    }
+
+    if alias.is_none() && rows.iter().any(|row| !row.is_object()) {
+      panic!("Data file {} has rows without fields, name them with `as`", file);
+    }
+
+    let reserved = match &alias {
+      Some(alias) => RESERVED_FIELDS.iter().find(|field| *field == alias),
+      None => RESERVED_FIELDS.iter().find(|field| rows.iter().any(|row| row.get(field).is_some())),
+    };
+
+    if let Some(field) = reserved {
+      panic!("Data file {} can't feed `{}`, it is set by the benchmark. Rename it or use `as`", file, field);
+    }
+
+    if strategy == HarrawStrategy::Unique {
+      hrw_with_rng(|rng| rows.shuffle(rng));
+    }
+
+    HarrawFeeder {
+      file,
+      rows,
+      strategy,
+      recycle,
+      alias,
+      exhausted: AtomicBool::new(false),
+    }
+  }
+
+  /// The row of the given iteration, `None` once the rows ran out and they can't be recycled.
+  fn hrw_row(&self, iteration: usize) -> Option<&Value> {
+    match self.strategy {
+      HarrawStrategy::Random => hrw_with_rng(|rng| self.rows.choose(rng)),
+      HarrawStrategy::Sequential | HarrawStrategy::Unique => {
+        if iteration < self.rows.len() || self.recycle {
+          self.rows.get(iteration % self.rows.len())
+        } else {
+          None
+        }
+      }
+    }
+  }
+
+  /// Exposes the row of the iteration in the context, under `as` or field by field.
+  pub fn hrw_feed(&self, iteration: usize, context: &mut Context, quiet: bool) -> bool {
+    let row = match self.hrw_row(iteration) {
+      Some(row) => row.clone(),
+      None => {
+        if !self.exhausted.swap(true, Ordering::Relaxed) && !quiet {
+          println!("{} Data file {} exhausted, skipping the remaining iterations", "WARNING!".yellow().bold(), self.file);
+        }
+        return false;
+      }
+    };
+
+    match (&self.alias, row) {
+      (Some(alias), row) => {
+        context.insert(alias.to_owned(), row);
+      }
+      (None, Value::Object(fields)) => context.extend(fields),
+      (None, _) => unreachable!(),
+    }
+    true
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serde_json::json;
+
+  fn hrw_feeder(text: &str) -> HarrawFeeder {
+    let docs = yaml_rust::YamlLoader::load_from_str(text).unwrap();
+    HarrawFeeder::new("benchmark.yml", &docs[0])
+  }
+
+  #[test]
+  fn hrw_feeds_sequential_rows() {
+    let feeder = hrw_feeder("---\nfile: ./fixtures/accounts.csv");
+    let mut context = Context::new();
+
+    assert!(feeder.hrw_feed(1, &mut context, true));
+    assert_eq!(context.get("email"), Some(&json!("mary@example.com")));
+
+    assert!(feeder.hrw_feed(3, &mut context, true));
+    assert_eq!(context.get("email"), Some(&json!("john@example.com")));
+  }
+
+  #[test]
+  fn hrw_stops_when_exhausted() {
+    let feeder = hrw_feeder("---\nfile: ./fixtures/accounts.jsonl\nas: account\non_exhausted: stop");
+    let mut context = Context::new();
+
+    assert!(feeder.hrw_feed(1, &mut context, true));
+    assert_eq!(context.get("account"), Some(&json!({"email": "mary@example.com", "address": {"city": "Porto"}})));
+    assert!(!feeder.hrw_feed(2, &mut context, true));
+  }
+
+  #[test]
+  fn hrw_unique_rows_are_not_reused() {
+    let feeder = hrw_feeder("---\nfile: ./fixtures/texts.txt\nas: word\nstrategy: unique\non_exhausted: stop");
+    let mut words: Vec<String> = (0..3).map(|iteration| feeder.hrw_row(iteration).unwrap().as_str().unwrap().to_string()).collect();
+    words.sort();
+
+    assert_eq!(words, vec!["bar", "baz", "foo"]);
+    assert!(feeder.hrw_row(3).is_none());
+  }
+
+  #[test]
+  fn hrw_unique_rows_stop_by_default() {
+    let feeder = hrw_feeder("---\nfile: ./fixtures/texts.txt\nas: word\nstrategy: unique");
+
+    assert!(feeder.hrw_row(2).is_some());
+    assert!(feeder.hrw_row(3).is_none());
+  }
+
+  #[test]
+  fn hrw_unique_rows_recycle_when_asked() {
+    let feeder = hrw_feeder("---\nfile: ./fixtures/texts.txt\nas: word\nstrategy: unique\non_exhausted: recycle");
+
+    assert_eq!(feeder.hrw_row(3), feeder.hrw_row(0));
+  }
+
+  #[test]
+  #[should_panic(expected = "can't feed `iterations`, it is set by the benchmark")]
+  fn hrw_reserved_names_are_rejected() {
+    hrw_feeder("---\nfile: ./fixtures/texts.txt\nas: iterations");
+  }
+
+  #[test]
+  #[should_panic(expected = "has rows without fields, name them with `as`")]
+  fn hrw_lines_need_a_name() {
+    hrw_feeder("---\nfile: ./fixtures/texts.txt");
+  }
+}
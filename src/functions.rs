@@ -0,0 +1,145 @@
+use chrono::format::{Item, StrftimeItems};
+use chrono::Utc;
+use rand::distributions::Alphanumeric;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde_json::{json, Value};
+use uuid::Builder;
+
+use crate::expression::{hrw_number, HarrawExpressionError};
+use crate::faker::hrw_fake;
+use crate::interpolator::hrw_value_to_string;
+use crate::random::hrw_with_rng;
+use crate::shared;
+
+static DEFAULT_TIMESTAMP_FORMAT: &str = "%Y-%m-%dT%H:%M:%SZ";
+
+/// Calls a built-in interpolation function like `{{ uuid() }}`.
+pub fn hrw_call(name: &str, args: &[Value]) -> Result<Value, HarrawExpressionError> {
+  match name {
+    "uuid" => {
+      hrw_arity(name, args, 0, 0)?;
+      Ok(json!(Builder::from_random_bytes(hrw_with_rng(|rng| rng.gen())).into_uuid().to_string()))
+    }
+    "random_int" => {
+      hrw_arity(name, args, 2, 2)?;
+      let (min, max) = (hrw_integer(name, &args[0])?, hrw_integer(name, &args[1])?);
+
+      if min > max {
+        return Err(HarrawExpressionError::Invalid(format!("`{name}` needs min <= max, got {min} and {max}")));
+      }
+      Ok(json!(hrw_with_rng(|rng| rng.gen_range(min..=max))))
+    }
+    "random_string" => {
+      hrw_arity(name, args, 1, 1)?;
+      let length = hrw_integer(name, &args[0])?.max(0) as usize;
+      let string: String = hrw_with_rng(|rng| rng.sample_iter(&Alphanumeric).take(length).map(char::from).collect());
+      Ok(json!(string))
+    }
+    "random_choice" => {
+      hrw_arity(name, args, 1, 1)?;
+      match &args[0] {
+        Value::Array(items) => hrw_with_rng(|rng| items.choose(rng).cloned()).ok_or_else(|| HarrawExpressionError::Invalid(format!("`{name}` needs a non empty list"))),
+        other => Err(HarrawExpressionError::Invalid(format!("`{name}` needs a list, got `{}`", hrw_value_to_string(other)))),
+      }
+    }
+    "faker" => {
+      hrw_arity(name, args, 1, 2)?;
+      let locale = args.get(1).map_or("en".to_string(), hrw_value_to_string);
+      hrw_with_rng(|rng| hrw_fake(&hrw_value_to_string(&args[0]), &locale, rng))
+    }
+    "now" => {
+      hrw_arity(name, args, 0, 0)?;
+      Ok(json!(Utc::now().timestamp()))
+    }
+    "now_ms" => {
+      hrw_arity(name, args, 0, 0)?;
+      Ok(json!(Utc::now().timestamp_millis()))
+    }
+    "timestamp" => {
+      hrw_arity(name, args, 0, 1)?;
+      let format = args.first().map_or(DEFAULT_TIMESTAMP_FORMAT.to_string(), hrw_value_to_string);
+
+      if StrftimeItems::new(&format).any(|item| matches!(item, Item::Error)) {
+        return Err(HarrawExpressionError::Invalid(format!("Invalid timestamp format `{format}`")));
+      }
+      Ok(json!(Utc::now().format(&format).to_string()))
+    }
+    "counter" => {
+      hrw_arity(name, args, 1, 1)?;
+      Ok(json!(shared::hrw_counter(&hrw_value_to_string(&args[0]))))
+    }
+    "shared" => {
+      hrw_arity(name, args, 1, 1)?;
+      let key = hrw_value_to_string(&args[0]);
+      shared::hrw_get(&key).ok_or_else(|| HarrawExpressionError::Undefined(format!("shared('{key}')")))
+    }
+    _ => Err(HarrawExpressionError::Invalid(format!("Unknown function `{name}`"))),
+  }
+}
+
+fn hrw_arity(name: &str, args: &[Value], min: usize, max: usize) -> Result<(), HarrawExpressionError> {
+  if args.len() < min || args.len() > max {
+    let expected = if min == max { min.to_string() } else { format!("{min} to {max}") };
+    return Err(HarrawExpressionError::Invalid(format!("`{name}` takes {expected} arguments, got {}", args.len())));
+  }
+  Ok(())
+}
+
+fn hrw_integer(name: &str, value: &Value) -> Result<i64, HarrawExpressionError> {
+  match hrw_number(value) {
+    Some(number) if number.fract() == 0.0 => Ok(number as i64),
+    _ => Err(HarrawExpressionError::Invalid(format!("`{name}` needs integers, got `{}`", hrw_value_to_string(value)))),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn hrw_generates_random_values() {
+    let uuid = hrw_call("uuid", &[]).unwrap();
+    assert_eq!(uuid.as_str().unwrap().len(), 36);
+    assert_eq!(&uuid.as_str().unwrap()[14..15], "4");
+
+    let number = hrw_call("random_int", &[json!(1), json!("3")]).unwrap().as_i64().unwrap();
+    assert!((1..=3).contains(&number));
+
+    let string = hrw_call("random_string", &[json!(16)]).unwrap();
+    assert!(string.as_str().unwrap().chars().all(|c| c.is_ascii_alphanumeric()));
+    assert_eq!(string.as_str().unwrap().len(), 16);
+
+    let choice = hrw_call("random_choice", &[json!(["a", "b"])]).unwrap();
+    assert!(choice == json!("a") || choice == json!("b"));
+  }
+
+  #[test]
+  fn hrw_generates_times() {
+    let now = hrw_call("now", &[]).unwrap().as_i64().unwrap();
+    let now_ms = hrw_call("now_ms", &[]).unwrap().as_i64().unwrap();
+    assert!(now_ms / 1000 - now <= 1);
+
+    let date = hrw_call("timestamp", &[json!("%Y-%m-%d")]).unwrap();
+    assert_eq!(date.as_str().unwrap().len(), 10);
+  }
+
+  #[test]
+  fn hrw_reads_shared_state() {
+    assert_eq!(hrw_call("counter", &[json!("functions")]), Ok(json!(1)));
+    assert_eq!(hrw_call("counter", &[json!("functions")]), Ok(json!(2)));
+
+    shared::hrw_set("functions", json!({"id": 7}));
+    assert_eq!(hrw_call("shared", &[json!("functions")]), Ok(json!({"id": 7})));
+    assert!(matches!(hrw_call("shared", &[json!("missing")]), Err(HarrawExpressionError::Undefined(_))));
+  }
+
+  #[test]
+  fn hrw_rejects_bad_calls() {
+    assert!(matches!(hrw_call("random_int", &[json!(1)]), Err(HarrawExpressionError::Invalid(_))));
+    assert!(matches!(hrw_call("random_int", &[json!(5), json!(1)]), Err(HarrawExpressionError::Invalid(_))));
+    assert!(matches!(hrw_call("random_choice", &[json!([])]), Err(HarrawExpressionError::Invalid(_))));
+    assert!(matches!(hrw_call("timestamp", &[json!("%Q")]), Err(HarrawExpressionError::Invalid(_))));
+    assert!(matches!(hrw_call("shout", &[]), Err(HarrawExpressionError::Invalid(_))));
+  }
+}
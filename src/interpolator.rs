@@ -1,9 +1,10 @@
 use colored::*;
 use lazy_static::lazy_static;
 use regex::{Captures, Regex};
-use serde_json::json;
+use serde_json::Value;
 
 use crate::benchmark::Context;
+use crate::expression::{self, HarrawExpressionError};
 
 static INTERPOLATION_PREFIX: &str = "{{";
 static INTERPOLATION_SUFFIX: &str = "}}";
@@ -11,9 +12,11 @@ static INTERPOLATION_SUFFIX: &str = "}}";
 
 lazy_static! {
     pub static ref INTERPOLATION_REGEX: Regex = {
-        let regexp = format!("{}{}{}", regex::escape(INTERPOLATION_PREFIX), r" *([a-zA-Z]+[a-zA-Z\-\._\$0-9\[\]]*) *", regex::escape(INTERPOLATION_SUFFIX));
+        let regexp = format!("{}{}{}", regex::escape(INTERPOLATION_PREFIX), r" *(.+?) *", regex::escape(INTERPOLATION_SUFFIX));
         Regex::new(regexp.as_str()).unwrap()
     };
+    static ref SINGLE_INTERPOLATION_REGEX: Regex = Regex::new(r"^\s*\{\{ *(.+?) *\}\}\s*$").unwrap();
+    static ref PATH_REGEX: Regex = Regex::new(r"^[a-zA-Z]+[a-zA-Z\-\._\$0-9\[\]]*$").unwrap();
 }
 
 pub struct HarrawInterpolator<'a> {
@@ -28,25 +31,119 @@ impl <'a> HarrawInterpolator <'a> {
 
     pub fn hrw_resolve(&self, url: &str, strict: bool) -> String {
         INTERPOLATION_REGEX.replace_all(url, |caps: &Captures| {
-            let capture = &caps[1];
-    
-            if let Some(item) = self.hrw_context_environment_interpolation(capture) {
-                return item;
+            match self.hrw_evaluate(&caps[1]) {
+                Ok(value) => hrw_value_to_string(&value),
+                Err(error) => {
+                    if strict {
+                        panic!("{}", error);
+                    }
+                    eprintln!("{} {}", "WARNING!".yellow().bold(), error);
+
+                    match error {
+                        // Not an expression at all, leave the text as it is
+                        HarrawExpressionError::Syntax(_) => caps[0].to_string(),
+                        _ => "".to_string(),
+                    }
+                }
             }
-    
-            if let Some(item) = self.hrw_resolve_environment_interpolation(capture) {
-                return item;
+        }).to_string()
+    }
+
+    /// Like `hrw_resolve`, but a text made of a single interpolation keeps the type of its value,
+    /// so `"{{ order.body }}"` stays an object.
+    pub fn hrw_resolve_value(&self, source: &str, strict: bool) -> Value {
+        let expression = match hrw_single_interpolation(source) {
+            Some(expression) => expression,
+            None => return Value::String(self.hrw_resolve(source, strict)),
+        };
+
+        match self.hrw_evaluate(expression) {
+            Ok(value) => value,
+            Err(error) => {
+                if strict {
+                    panic!("{}", error);
+                }
+                eprintln!("{} {}", "WARNING!".yellow().bold(), error);
+
+                match error {
+                    HarrawExpressionError::Syntax(_) => Value::String(source.to_string()),
+                    _ => Value::Null,
+                }
             }
-    
-            if strict {
-                panic!("Unknown '{}' variable!", &capture);
+        }
+    }
+
+    /// Evaluates the inside of a `{{ ... }}`. Plain paths are looked up as they are,
+    /// so names with dashes like `Transfer-Encoding` are never read as a subtraction.
+    /// An unknown `page-1` is, since a dash followed by a digit cannot start a name.
+    pub fn hrw_evaluate(&self, source: &str) -> Result<Value, HarrawExpressionError> {
+        let is_path = PATH_REGEX.is_match(source);
+
+        if is_path {
+            if let Some(value) = self.hrw_lookup(source) {
+                return Ok(value);
             }
-            eprintln!("{} Unknown '{}' variable!", "WARNING!".yellow().bold(), &capture);
-            "".to_string()
-        }).to_string()
+            if source.split('-').skip(1).any(|rest| !rest.starts_with(|c: char| c.is_ascii_digit())) {
+                return Err(HarrawExpressionError::Undefined(source.to_string()));
+            }
+        }
+
+        match expression::hrw_parse(source) {
+            Ok(expression) => expression.hrw_evaluate(&|path| self.hrw_lookup(path)),
+            Err(_) if is_path => Err(HarrawExpressionError::Undefined(source.to_string())),
+            Err(error) => Err(error),
+        }
     }
-    
-    
+
+    /// Evaluates a `when:` condition like `{{ login.status }} == 200 and {{ user.admin }}`.
+    /// Every interpolation is evaluated first and the remaining text is read as an expression.
+    /// Interpolations inside a quoted string, like `'{{ role }}' == 'admin'`, become part of its text.
+    pub fn hrw_condition(&self, source: &str) -> Result<bool, HarrawExpressionError> {
+        let mut values: Vec<Value> = Vec::new();
+        let mut error = None;
+        let mut rewritten = String::new();
+        let mut quote = None;
+        let mut last = 0;
+
+        for caps in INTERPOLATION_REGEX.captures_iter(source) {
+            let whole = caps.get(0).unwrap();
+            let text = &source[last..whole.start()];
+            quote = hrw_quote_after(text, quote);
+            rewritten.push_str(text);
+            last = whole.end();
+
+            let value = match self.hrw_evaluate(&caps[1]) {
+                Ok(value) => value,
+                Err(e) => {
+                    error.get_or_insert(e);
+                    Value::Null
+                }
+            };
+
+            match quote {
+                Some(quote) => rewritten.push_str(&hrw_value_to_string(&value).replace('\\', "\\\\").replace(quote, &format!("\\{quote}"))),
+                None => {
+                    values.push(value);
+                    rewritten.push_str(&format!("__hrw_{}", values.len() - 1));
+                }
+            }
+        }
+        rewritten.push_str(&source[last..]);
+
+        if let Some(error) = error {
+            return Err(error);
+        }
+
+        let lookup = |path: &str| match path.strip_prefix("__hrw_").and_then(|index| index.parse::<usize>().ok()) {
+            Some(index) => values.get(index).cloned(),
+            None => self.hrw_lookup(path),
+        };
+        let value = expression::hrw_parse(&rewritten)?.hrw_evaluate(&lookup)?;
+
+        Ok(expression::hrw_truthy(&value))
+    }
+
+
     fn hrw_resolve_environment_interpolation(&self, value: &str) -> Option<String> {
         match std::env::vars().find(|tuple| tuple.0 == value) {
             Some(tuple) => Some(tuple.1),
@@ -55,20 +152,67 @@ impl <'a> HarrawInterpolator <'a> {
     }
     
     
-    fn hrw_context_environment_interpolation(&self, value: &str) -> Option<String> {
-        let val: String = format!("/{}", value.replace(['.', '['], "/").replace(']', ""));
-
-        if let Some(item) = json!(self.context).pointer(&val).to_owned() {
-            return Some(match item.to_owned() {
-                serde_json::Value::Null => "".to_owned(),
-                serde_json::Value::Bool(v) => v.to_string(),
-                serde_json::Value::Number(v) => v.to_string(),
-                serde_json::Value::String(v) => v,
-                serde_json::Value::Array(v) => serde_json::to_string(&v).unwrap(),
-                serde_json::Value::Object(v) => serde_json::to_string(&v).unwrap(),
-            });
+    /// Looks up a dotted/bracket path in the context, falling back to an
+    /// environment variable with the same name.
+    pub fn hrw_lookup(&self, path: &str) -> Option<Value> {
+        let pointer = hrw_json_pointer(path);
+        let (head, rest) = pointer[1..].split_once('/').map_or((&pointer[1..], None), |(head, rest)| (head, Some(rest)));
+        let item = self.context.get(head).and_then(|value| match rest {
+            Some(rest) => value.pointer(&format!("/{rest}")),
+            None => Some(value),
+        });
+
+        if let Some(item) = item {
+            return Some(item.to_owned());
         }
-        None
+        self.hrw_resolve_environment_interpolation(path).map(Value::String)
+    }
+}
+
+
+/// The inside of a text made of exactly one interpolation, like `foo.body` for `"{{ foo.body }}"`.
+/// The quote of the string literal still open after `text`, given the one open before it.
+fn hrw_quote_after(text: &str, mut quote: Option<char>) -> Option<char> {
+    let mut chars = text.chars();
+
+    while let Some(c) = chars.next() {
+        match quote {
+            Some(_) if c == '\\' => {
+                chars.next();
+            }
+            Some(open) if c == open => quote = None,
+            None if c == '\'' || c == '"' => quote = Some(c),
+            _ => {}
+        }
+    }
+    quote
+}
+
+pub fn hrw_single_interpolation(source: &str) -> Option<&str> {
+    let expression = SINGLE_INTERPOLATION_REGEX.captures(source)?.get(1)?.as_str();
+
+    (!expression.contains(INTERPOLATION_SUFFIX)).then_some(expression)
+}
+
+/// Turns an interpolation path like `foo.body[1].id` into the JSON pointer `/foo/body/1/id`.
+pub fn hrw_json_pointer(path: &str) -> String {
+    let pointer = path.replace(['.', '['], "/").replace(']', "");
+
+    if pointer.starts_with('/') {
+        pointer
+    } else {
+        format!("/{pointer}")
+    }
+}
+
+pub fn hrw_value_to_string(value: &Value) -> String {
+    match value {
+        Value::Null => "".to_owned(),
+        Value::Bool(v) => v.to_string(),
+        Value::Number(v) => v.to_string(),
+        Value::String(v) => v.to_owned(),
+        Value::Array(v) => serde_json::to_string(&v).unwrap(),
+        Value::Object(v) => serde_json::to_string(&v).unwrap(),
     }
 }
 
@@ -97,7 +241,7 @@ mod tests {
         let mut context: Context = Context::new();
 
         context.insert(String::from("Null"), serde_json::Value::Null);
-        context.insert(String::from("bool"), json!(true));
+        context.insert(String::from("Bool"), json!(true));
         context.insert(String::from("Number"), json!(12));
         context.insert(String::from("String"), json!("string"));
         context.insert(String::from("Array"), json!(["a", "b", "c"]));
@@ -157,6 +301,26 @@ mod tests {
         assert_eq!(interpolated, "http://example.com/postalcode/{{ 5digitzip }}/view/{{ 5digitzip }}");
     }
 
+    #[test]
+    #[should_panic(expected = "Invalid expression")]
+    fn hrw_rejects_invalid_expressions_when_strict() {
+        let context: Context = Context::new();
+        HarrawInterpolator::new(&context).hrw_resolve("http://example.com/postalcode/{{ 5digitzip }}", true);
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid expression")]
+    fn hrw_rejects_invalid_value_expressions_when_strict() {
+        let context: Context = Context::new();
+        HarrawInterpolator::new(&context).hrw_resolve_value("{{ 5digitzip }}", true);
+    }
+
+    #[test]
+    fn hrw_keeps_invalid_value_expressions_when_relaxed() {
+        let context: Context = Context::new();
+        assert_eq!(HarrawInterpolator::new(&context).hrw_resolve_value("{{ 5digitzip }}", false), json!("{{ 5digitzip }}"));
+    }
+
     #[test]
     fn hrw_interpolates_environment_variables() {
         std::env::set_var("FOO", "BAR");
@@ -166,4 +330,51 @@ mod tests {
         let interpolated = interpolator.hrw_resolve(&url, false);
         assert_eq!(interpolated, "http://example.com/postalcode/BAR");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn hrw_interpolates_expressions() {
+        let mut context: Context = Context::new();
+        context.insert(String::from("user"), json!({"name": "Mary Jane", "page": 2}));
+        let interpolator = HarrawInterpolator::new(&context);
+        let url = String::from("/search?q={{ user.name | urlencode }}&page={{ user.page + 1 }}&sort={{ user.sort | default('name') }}&ref={{ 'p-' ~ user.page }}");
+        let interpolated = interpolator.hrw_resolve(&url, true);
+        assert_eq!(interpolated, "/search?q=Mary%20Jane&page=3&sort=name&ref=p-2");
+    }
+
+    #[test]
+    fn hrw_interpolates_subtractions_without_spaces() {
+        let mut context: Context = Context::new();
+        context.insert(String::from("page"), json!(3));
+        context.insert(String::from("user-2"), json!("second"));
+        let interpolator = HarrawInterpolator::new(&context);
+
+        assert_eq!(interpolator.hrw_resolve("{{ page-1 }}/{{ page - 1 }}/{{ user-2 }}", true), "2/2/second");
+        assert_eq!(interpolator.hrw_evaluate("pages-1"), Err(HarrawExpressionError::Undefined("pages".to_string())));
+        assert_eq!(interpolator.hrw_evaluate("Content-Type"), Err(HarrawExpressionError::Undefined("Content-Type".to_string())));
+    }
+
+    #[test]
+    fn hrw_evaluates_conditions() {
+        let mut context: Context = Context::new();
+        context.insert(String::from("login"), json!({"status": 200, "body": {"roles": ["admin"], "role": "admin", "name": "O'Brien"}}));
+        let interpolator = HarrawInterpolator::new(&context);
+
+        assert_eq!(interpolator.hrw_condition("{{ login.status }} == 200"), Ok(true));
+        assert_eq!(interpolator.hrw_condition("{{ login.status }} != 200 or 'admin' in {{ login.body.roles }}"), Ok(true));
+        assert_eq!(interpolator.hrw_condition("login.status >= 400"), Ok(false));
+        assert_eq!(interpolator.hrw_condition("{{ login.body.token | default('') }}"), Ok(false));
+        assert!(matches!(interpolator.hrw_condition("{{ logout.status }} == 200"), Err(HarrawExpressionError::Undefined(_))));
+        assert!(matches!(interpolator.hrw_condition("{{ login.status }} ==="), Err(HarrawExpressionError::Syntax(_))));
+        assert_eq!(interpolator.hrw_condition("'{{ login.body.role }}' == 'admin'"), Ok(true));
+        assert_eq!(interpolator.hrw_condition("\"role: {{ login.body.role }}\" == 'role: admin' and '{{ login.status }}' != '{{ login.body.role }}'"), Ok(true));
+        assert_eq!(interpolator.hrw_condition("'{{ login.body.name }}' == \"O'Brien\""), Ok(true));
+    }
+
+    #[test]
+    #[should_panic(expected = "Unknown filter `shout`")]
+    fn hrw_interpolates_unknown_filter() {
+        let context: Context = Context::new();
+        let interpolator = HarrawInterpolator::new(&context);
+        interpolator.hrw_resolve("{{ 'foo' | shout }}", true);
+    }
+}
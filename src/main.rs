@@ -1,15 +1,24 @@
+mod assertion;
 mod reader;
 mod writer;
 mod config;
 mod interpolator;
+mod expression;
+mod functions;
+mod faker;
+mod random;
+mod feeder;
+mod shared;
 mod benchmark;
+mod schema;
 mod tags;
 mod checker;
 mod backend;
 mod service;
 
 
-use crate::service::HarrawReport;
+use crate::benchmark::Reports;
+use crate::service::{HarrawCheckReport, HarrawReport, HarrawTransactionReport};
 use clap::crate_version;
 use clap::{App, Arg};
 use colored::*;
@@ -35,6 +44,11 @@ fn main() {
   let skip_tags_option = matches.value_of("skip-tags");
   let list_tags = matches.is_present("list-tags");
   let list_tasks = matches.is_present("list-tasks");
+  let checks_threshold_option = matches.value_of("checks-threshold");
+  let seed_option = matches.value_of("seed").map(|s| s.parse::<u64>().expect("Invalid seed"));
+  let vars: Vec<&str> = matches.values_of("var").map_or_else(Vec::new, |values| values.collect());
+  let overrides = config::hrw_read_overrides(matches.value_of("vars-file"), &vars);
+  let environment_option = matches.value_of("env");
 
   #[cfg(windows)]
   let _ = control::set_virtual_terminal(true);
@@ -51,13 +65,29 @@ fn main() {
     process::exit(0);
   };
 
-  let benchmark_result = benchmark::hrw_execute(benchmark_file, report_path_option, relaxed_interpolations, no_check_certificate, quiet, nanosec, timeout, verbose, &tags);
+  let benchmark_result = benchmark::hrw_execute(benchmark_file, report_path_option, relaxed_interpolations, no_check_certificate, quiet, nanosec, timeout, verbose, seed_option, &overrides, environment_option, &tags);
   let list_reports = benchmark_result.reports;
   let duration = benchmark_result.duration;
 
-  hrw_show_stats(&list_reports, stats_option, nanosec, duration);
-  hrw_compare_benchmark(&list_reports, compare_path_option, threshold_option);
+  hrw_show_stats(&list_reports, stats_option, nanosec, duration, benchmark_result.seed);
+  hrw_show_transactions(&list_reports, stats_option, nanosec);
+  hrw_show_skipped(&list_reports, stats_option);
+  let checks_passed = hrw_show_checks(&list_reports, stats_option, checks_threshold_option);
 
+  if benchmark_result.aborted {
+    println!("{}", "Benchmark aborted!".red().bold());
+  }
+
+  // Compare even when checks failed, so regressions are reported either way
+  let compare_passed = hrw_compare_benchmark(&list_reports, compare_path_option, threshold_option);
+
+  if !checks_passed || benchmark_result.aborted {
+    println!("{} {}", "Replay this run with".yellow(), format!("--seed {}", benchmark_result.seed).purple());
+  }
+
+  if !checks_passed || benchmark_result.aborted || !compare_passed {
+    process::exit(1);
+  }
   process::exit(0)
 }
 
@@ -72,10 +102,15 @@ fn app_args<'a>() -> clap::ArgMatches<'a> {
       .arg(Arg::with_name("report").short("r").long("report").help("Sets a report file").takes_value(true).conflicts_with("compare"))
       .arg(Arg::with_name("compare").short("c").long("compare").help("Sets a compare file").takes_value(true).conflicts_with("report"))
       .arg(Arg::with_name("threshold").short("t").long("threshold").help("Sets a threshold value in ms amongst the compared file").takes_value(true).conflicts_with("report"))
+      .arg(Arg::with_name("checks-threshold").long("checks-threshold").help("Sets the minimum percentage of passed checks, exits with 1 below it (default: 100)").takes_value(true))
+      .arg(Arg::with_name("env").short("e").long("env").help("Selects an entry of the benchmark environments").takes_value(true))
+      .arg(Arg::with_name("var").long("var").help("Sets a variable as key=value, overriding the vars file and the plan vars").takes_value(true).multiple(true).number_of_values(1))
+      .arg(Arg::with_name("vars-file").long("vars-file").help("Sets variables from a YAML or JSON file, overriding the plan vars").takes_value(true))
+      .arg(Arg::with_name("seed").long("seed").help("Sets the random seed, to replay shuffles and random values of a previous run").takes_value(true))
       .arg(Arg::with_name("relaxed-interpolations").long("relaxed-interpolations").help("Do not panic if an interpolation is not present. (Not recommended)").takes_value(false))
       .arg(Arg::with_name("no-check-certificate").long("no-check-certificate").help("Disables SSL certification check. (Not recommended)").takes_value(false))
-      .arg(Arg::with_name("tags").long("tags").help("Tags to include").takes_value(true))
-      .arg(Arg::with_name("skip-tags").long("skip-tags").help("Tags to exclude").takes_value(true))
+      .arg(Arg::with_name("tags").long("tags").help("Tags to include, like 'smoke and not slow'").takes_value(true))
+      .arg(Arg::with_name("skip-tags").long("skip-tags").help("Tags to exclude, like 'flaky or wip'").takes_value(true))
       .arg(Arg::with_name("list-tags").long("list-tags").help("List all benchmark tags").takes_value(false).conflicts_with_all(&["tags", "skip-tags"]))
       .arg(Arg::with_name("list-tasks").long("list-tasks").help("List benchmark tasks (executes --tags/--skip-tags filter)").takes_value(false))
       .arg(Arg::with_name("quiet").short("q").long("quiet").help("Disables output").takes_value(false))
@@ -139,14 +174,15 @@ fn app_args<'a>() -> clap::ArgMatches<'a> {
     }
   }
   
-  fn hrw_show_stats(list_reports: &[Vec<HarrawReport>], stats_option: bool, nanosec: bool, duration: f64) {
+  fn hrw_show_stats(list_reports: &[Reports], stats_option: bool, nanosec: bool, duration: f64, seed: u64) {
     if !stats_option {
       return;
     }
   
     let mut group_by_name = LinkedHashMap::new();
+    let allreports: Vec<HarrawReport> = list_reports.iter().flat_map(|r| r.requests.clone()).collect();
   
-    for req in list_reports.concat() {
+    for req in allreports.iter().cloned() {
       group_by_name.entry(req.name.clone()).or_insert_with(Vec::new).push(req);
     }
   
@@ -166,7 +202,6 @@ fn app_args<'a>() -> clap::ArgMatches<'a> {
     }
   
     // compute global stats
-    let allreports = list_reports.concat();
     let global_stats = hrw_compute_stats(&allreports);
     let requests_per_second = global_stats.total_requests as f64 / duration;
   
@@ -182,19 +217,166 @@ fn app_args<'a>() -> clap::ArgMatches<'a> {
     println!("{:width2$} {}", "99.0'th percentile".yellow(), hrw_format_time(global_stats.value_at_quantile(0.99), nanosec).purple(), width2 = 25);
     println!("{:width2$} {}", "99.5'th percentile".yellow(), hrw_format_time(global_stats.value_at_quantile(0.995), nanosec).purple(), width2 = 25);
     println!("{:width2$} {}", "99.9'th percentile".yellow(), hrw_format_time(global_stats.value_at_quantile(0.999), nanosec).purple(), width2 = 25);
+    println!("{:width2$} {}", "Seed".yellow(), seed.to_string().purple(), width2 = 25);
   }
   
-  fn hrw_compare_benchmark(list_reports: &[Vec<HarrawReport>], compare_path_option: Option<&str>, threshold_option: Option<&str>) {
+  fn hrw_show_transactions(list_reports: &[Reports], stats_option: bool, nanosec: bool) {
+    let alltransactions: Vec<HarrawTransactionReport> = list_reports.iter().flat_map(|r| r.transactions.clone()).collect();
+
+    if !stats_option || alltransactions.is_empty() {
+      return;
+    }
+
+    let mut group_by_name = LinkedHashMap::new();
+
+    for transaction in alltransactions.iter() {
+      group_by_name.entry(transaction.name.clone()).or_insert_with(Vec::new).push(transaction.clone());
+    }
+
+    for (name, transactions) in group_by_name {
+      let mut hist = Histogram::<u64>::new_with_bounds(1, 60 * 60 * 1000, 2).unwrap();
+
+      for transaction in transactions.iter() {
+        hist += (transaction.duration * 1_000.0) as u64;
+      }
+
+      let passed = transactions.iter().filter(|t| t.passed).count();
+      let median = hist.value_at_quantile(0.5) as f64 / 1_000.0;
+      let mean = hist.mean() / 1_000.0;
+      let percentile = hist.value_at_quantile(0.99) as f64 / 1_000.0;
+
+      println!();
+      println!("{:width$} {:width2$} {}", name.green(), "Total transactions".yellow(), transactions.len().to_string().purple(), width = 25, width2 = 25);
+      println!("{:width$} {:width2$} {}", name.green(), "Successful transactions".yellow(), passed.to_string().purple(), width = 25, width2 = 25);
+      println!("{:width$} {:width2$} {}", name.green(), "Failed transactions".yellow(), (transactions.len() - passed).to_string().purple(), width = 25, width2 = 25);
+      println!("{:width$} {:width2$} {}", name.green(), "Median transaction time".yellow(), hrw_format_time(median, nanosec).purple(), width = 25, width2 = 25);
+      println!("{:width$} {:width2$} {}", name.green(), "Average transaction time".yellow(), hrw_format_time(mean, nanosec).purple(), width = 25, width2 = 25);
+      println!("{:width$} {:width2$} {}", name.green(), "99.0'th percentile".yellow(), hrw_format_time(percentile, nanosec).purple(), width = 25, width2 = 25);
+    }
+  }
+
+  fn hrw_show_skipped(list_reports: &[Reports], stats_option: bool) {
+    let allskipped: Vec<String> = list_reports.iter().flat_map(|r| r.skipped.clone()).collect();
+
+    if !stats_option || allskipped.is_empty() {
+      return;
+    }
+
+    let mut group_by_name = LinkedHashMap::new();
+
+    for name in allskipped.iter() {
+      *group_by_name.entry(name.clone()).or_insert(0) += 1;
+    }
+
+    println!();
+    for (name, count) in group_by_name {
+      println!("{:width$} {:width2$} {}", name.green(), "Skipped".yellow(), count.to_string().purple(), width = 25, width2 = 25);
+    }
+    println!("{:width2$} {}", "Skipped steps".yellow(), allskipped.len().to_string().purple(), width2 = 25);
+  }
+
+  fn hrw_checks_percentage(checks: &[HarrawCheckReport]) -> f64 {
+    let passed = checks.iter().filter(|c| c.passed).count();
+    100.0 * passed as f64 / checks.len() as f64
+  }
+
+  fn hrw_show_checks(list_reports: &[Reports], stats_option: bool, checks_threshold_option: Option<&str>) -> bool {
+    let allchecks: Vec<HarrawCheckReport> = list_reports.iter().flat_map(|r| r.checks.clone()).collect();
+
+    if allchecks.is_empty() {
+      return true;
+    }
+
+    if stats_option {
+      let mut group_by_name = LinkedHashMap::new();
+
+      for check in allchecks.iter() {
+        group_by_name.entry(check.name.clone()).or_insert_with(Vec::new).push(check.clone());
+      }
+
+      println!();
+      for (name, checks) in group_by_name {
+        let passed = checks.iter().filter(|c| c.passed).count();
+        let summary = format!("{:.1}% ({}/{})", hrw_checks_percentage(&checks), passed, checks.len());
+        println!("{:width$} {:width2$} {}", name.green(), "Checks passed".yellow(), summary.purple(), width = 25, width2 = 25);
+      }
+    }
+
+    let percentage = hrw_checks_percentage(&allchecks);
+    let threshold = checks_threshold_option.map_or(100.0, |t| t.parse::<f64>().expect("Invalid checks threshold"));
+    let summary = format!("{percentage:.1}% passed");
+
+    println!();
+    if percentage < threshold {
+      println!("{:width2$} {} {}", "Checks".yellow(), summary.red(), format!("(threshold {threshold}%)").red(), width2 = 25);
+      false
+    } else {
+      println!("{:width2$} {}", "Checks".yellow(), summary.purple(), width2 = 25);
+      true
+    }
+  }
+
+  /// Whether the run is within the threshold of the compared report, or nothing was compared.
+  fn hrw_compare_benchmark(list_reports: &[Reports], compare_path_option: Option<&str>, threshold_option: Option<&str>) -> bool {
     if let Some(compare_path) = compare_path_option {
       if let Some(threshold) = threshold_option {
-        let compare_result = checker::hrw_compare(list_reports, compare_path, threshold);
-  
-        match compare_result {
-          Ok(_) => process::exit(0),
-          Err(_) => process::exit(1),
-        }
+        checker::hrw_compare(list_reports, compare_path, threshold).is_ok()
       } else {
         panic!("Threshold needed!");
       }
+    } else {
+      true
     }
-  }
\ No newline at end of file
+  }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::service::HarrawReports;
+
+  fn hrw_checks(results: &[bool]) -> Vec<Reports> {
+    let checks = results.iter().map(|passed| HarrawCheckReport { name: "Check".to_string(), passed: *passed }).collect();
+
+    vec![HarrawReports { checks, ..HarrawReports::default() }]
+  }
+
+  #[test]
+  fn hrw_checks_percentage_counts_passed() {
+    assert_eq!(hrw_checks_percentage(&hrw_checks(&[true, true, false, true])[0].checks), 75.0);
+    assert_eq!(hrw_checks_percentage(&hrw_checks(&[false, false])[0].checks), 0.0);
+  }
+
+  #[test]
+  fn hrw_checks_threshold_defaults_to_all_passing() {
+    assert!(hrw_show_checks(&hrw_checks(&[true, true]), false, None));
+    assert!(!hrw_show_checks(&hrw_checks(&[true, false]), false, None));
+  }
+
+  #[test]
+  fn hrw_checks_threshold_allows_failures() {
+    let reports = hrw_checks(&[true, true, true, false]);
+
+    assert!(hrw_show_checks(&reports, false, Some("75")));
+    assert!(!hrw_show_checks(&reports, false, Some("80")));
+  }
+
+  #[test]
+  fn hrw_checks_threshold_without_checks() {
+    assert!(hrw_show_checks(&[], false, Some("100")));
+  }
+
+  #[test]
+  fn hrw_compares_even_with_failed_checks() {
+    let path = std::env::temp_dir().join("harraw_compare_report.yml");
+    std::fs::write(&path, "- name: Fetch users\n  duration: 10.0\n").unwrap();
+    let path = path.to_str().unwrap();
+
+    let mut reports = hrw_checks(&[false]);
+    reports[0].requests.push(HarrawReport { name: "Fetch users".to_string(), duration: 500.0, status: 200 });
+
+    assert!(!hrw_show_checks(&reports, false, None));
+    assert!(!hrw_compare_benchmark(&reports, Some(path), Some("100")));
+    assert!(hrw_compare_benchmark(&reports, Some(path), Some("1000")));
+    assert!(hrw_compare_benchmark(&reports, None, None));
+  }
+}
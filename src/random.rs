@@ -0,0 +1,86 @@
+use std::cell::RefCell;
+use std::future::Future;
+use std::sync::Mutex;
+
+use lazy_static::lazy_static;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+lazy_static! {
+  static ref SEED: Mutex<u64> = Mutex::new(rand::random());
+  static ref PLAN_RNG: Mutex<StdRng> = Mutex::new(StdRng::seed_from_u64(*SEED.lock().unwrap()));
+}
+
+tokio::task_local! {
+  static ITERATION_RNG: RefCell<StdRng>;
+}
+
+/// Seeds every random decision of the run: expansion time shuffles and picks
+/// as well as the per iteration generators used by interpolation functions.
+pub fn hrw_seed(seed: u64) {
+  *SEED.lock().unwrap() = seed;
+  *PLAN_RNG.lock().unwrap() = StdRng::seed_from_u64(seed);
+}
+
+/// Each iteration gets its own generator, so the values it draws don't depend
+/// on how iterations interleave.
+pub async fn hrw_scope_iteration<F: Future>(iteration: i64, future: F) -> F::Output {
+  let rng = hrw_iteration_rng(*SEED.lock().unwrap(), iteration);
+
+  ITERATION_RNG.scope(RefCell::new(rng), future).await
+}
+
+fn hrw_iteration_rng(seed: u64, iteration: i64) -> StdRng {
+  StdRng::seed_from_u64(seed ^ (iteration as u64).wrapping_add(1).wrapping_mul(0x9E37_79B9_7F4A_7C15))
+}
+
+/// Gives a step of a `parallel` block its own generator. It is drawn right away from the
+/// current one, so the steps draw the same values however they interleave.
+pub fn hrw_scope_child<F: Future>(future: F) -> impl Future<Output = F::Output> {
+  let rng = hrw_with_rng(|rng| StdRng::seed_from_u64(rng.gen()));
+
+  ITERATION_RNG.scope(RefCell::new(rng), future)
+}
+
+/// Runs `f` with the generator of the current iteration, or the plan one outside of iterations.
+pub fn hrw_with_rng<T>(f: impl FnOnce(&mut StdRng) -> T) -> T {
+  let mut f = Some(f);
+
+  if let Ok(value) = ITERATION_RNG.try_with(|rng| (f.take().unwrap())(&mut rng.borrow_mut())) {
+    return value;
+  }
+  (f.take().unwrap())(&mut PLAN_RNG.lock().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::benchmark::hrw_test_block_on;
+
+  #[test]
+  fn hrw_iterations_are_reproducible() {
+    let first: u64 = hrw_iteration_rng(42, 3).gen();
+    let second: u64 = hrw_iteration_rng(42, 3).gen();
+    let other: u64 = hrw_iteration_rng(42, 4).gen();
+
+    assert_eq!(first, second);
+    assert_ne!(first, other);
+  }
+
+  #[test]
+  fn hrw_parallel_steps_are_reproducible() {
+    let draw = || ITERATION_RNG.scope(RefCell::new(hrw_iteration_rng(42, 3)), async {
+      let first = hrw_scope_child(async { hrw_with_rng(|rng| rng.gen::<u64>()) });
+      let second = hrw_scope_child(async { hrw_with_rng(|rng| rng.gen::<u64>()) });
+
+      // Polled in reverse order, the steps still get the generators they were created with
+      let second = second.await;
+      (first.await, second)
+    });
+
+    let (first, second) = hrw_test_block_on(draw());
+
+    assert_ne!(first, second);
+    assert_eq!(hrw_test_block_on(draw()), (first, second));
+  }
+}
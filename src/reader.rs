@@ -93,4 +93,48 @@ pub fn hrw_read_csv_file_as_yml(filepath: &str, quote: u8) -> yaml_rust::yaml::A
         }
     }
     items
-}
\ No newline at end of file
+}
+
+pub fn hrw_read_jsonl_file(filepath: &str) -> Vec<serde_json::Value> {
+    let content = hrw_read_file(filepath);
+
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(number, line)| serde_json::from_str(line).unwrap_or_else(|e| panic!("couldn't parse {} line {}: {}", filepath, number + 1, e)))
+        .collect()
+}
+
+pub fn hrw_read_json_file(filepath: &str) -> Vec<serde_json::Value> {
+    let content = hrw_read_file(filepath);
+
+    match serde_json::from_str(&content) {
+        Ok(serde_json::Value::Array(items)) => items,
+        Ok(_) => panic!("{} needs to be a JSON array", filepath),
+        Err(e) => panic!("couldn't parse {}: {}", filepath, e),
+    }
+}
+
+pub fn hrw_read_json_file_as_yml(filepath: &str) -> yaml_rust::yaml::Array {
+    hrw_read_json_file(filepath).into_iter().map(hrw_json_to_yaml).collect()
+}
+
+pub fn hrw_read_jsonl_file_as_yml(filepath: &str) -> yaml_rust::yaml::Array {
+    hrw_read_jsonl_file(filepath).into_iter().map(hrw_json_to_yaml).collect()
+}
+
+/// Keeps nested objects and lists, so they can be reached as `item.address.city`.
+pub fn hrw_json_to_yaml(value: serde_json::Value) -> yaml_rust::Yaml {
+    match value {
+        serde_json::Value::Null => yaml_rust::Yaml::Null,
+        serde_json::Value::Bool(b) => yaml_rust::Yaml::Boolean(b),
+        serde_json::Value::Number(n) => match n.as_i64() {
+            Some(i) => yaml_rust::Yaml::Integer(i),
+            None => yaml_rust::Yaml::Real(n.to_string()),
+        },
+        serde_json::Value::String(s) => yaml_rust::Yaml::String(s),
+        serde_json::Value::Array(items) => yaml_rust::Yaml::Array(items.into_iter().map(hrw_json_to_yaml).collect()),
+        serde_json::Value::Object(map) => yaml_rust::Yaml::Hash(map.into_iter().map(|(key, value)| (yaml_rust::Yaml::String(key), hrw_json_to_yaml(value))).collect()),
+    }
+}
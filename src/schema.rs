@@ -0,0 +1,76 @@
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+
+use jsonschema::Validator;
+use serde_json::Value;
+
+use crate::reader;
+
+/// A JSON Schema loaded from a file next to the benchmark.
+#[derive(Clone)]
+pub struct HarrawSchema {
+  path: String,
+  validator: Arc<Validator>,
+}
+
+impl HarrawSchema {
+  pub fn new(parent_path: &str, schema_path: &str) -> HarrawSchema {
+    let schema_filepath = Path::new(parent_path).with_file_name(schema_path);
+    let final_path = schema_filepath.to_str().unwrap();
+    let content = reader::hrw_read_file(final_path);
+    let schema: Value = serde_json::from_str(&content).unwrap_or_else(|e| panic!("couldn't parse schema {}: {}", final_path, e));
+    let validator = jsonschema::validator_for(&schema).unwrap_or_else(|e| panic!("invalid schema {}: {}", final_path, e));
+
+    HarrawSchema {
+      path: schema_path.to_string(),
+      validator: Arc::new(validator),
+    }
+  }
+
+  /// Lists every violation as `pointer: message`.
+  pub fn hrw_validate(&self, instance: &Value) -> Result<(), String> {
+    let violations: Vec<String> = self
+      .validator
+      .iter_errors(instance)
+      .map(|error| {
+        let pointer = error.instance_path.to_string();
+        let pointer = if pointer.is_empty() { "/".to_string() } else { pointer };
+        format!("{pointer}: {error}")
+      })
+      .collect();
+
+    if violations.is_empty() {
+      Ok(())
+    } else {
+      Err(violations.join(", "))
+    }
+  }
+}
+
+impl fmt::Debug for HarrawSchema {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "HarrawSchema({})", self.path)
+  }
+}
+
+impl fmt::Display for HarrawSchema {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{}", self.path)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serde_json::json;
+
+  #[test]
+  fn hrw_validates_against_a_schema_file() {
+    let schema = HarrawSchema::new("benchmark.yml", "./fixtures/user.schema.json");
+
+    assert!(schema.hrw_validate(&json!({"id": 2, "name": "John"})).is_ok());
+    assert_eq!(schema.hrw_validate(&json!({"id": "2", "name": "John"})), Err("/id: \"2\" is not of type \"integer\"".to_string()));
+    assert!(schema.hrw_validate(&json!({"id": 2})).unwrap_err().starts_with("/: \"name\" is a required property"));
+  }
+}
@@ -1,10 +1,10 @@
 use async_trait::async_trait;
 use colored::*;
-use serde_json::json;
 use yaml_rust::Yaml;
 
-use crate::service::hrw_extract;
-use crate::service::HarrawRunnable;
+use crate::assertion::HarrawAssertion;
+use crate::service::{hrw_extract, hrw_extract_optional, hrw_read_on_error, hrw_record_check};
+use crate::service::{HarrawFlow, HarrawRunnable};
 use crate::benchmark::{Context, Pool, Reports};
 use crate::config::HarrawConfig;
 use crate::interpolator;
@@ -13,7 +13,8 @@ use crate::interpolator;
 pub struct HarrawAssert {
   name: String,
   key: String,
-  value: String,
+  assertion: HarrawAssertion,
+  on_fail: Option<HarrawFlow>,
 }
 
 impl HarrawAssert {
@@ -21,28 +22,26 @@ impl HarrawAssert {
     item["assert"].as_hash().is_some()
   }
 
-  pub fn new(item: &Yaml, _with_item: Option<Yaml>) -> HarrawAssert {
+  pub fn new(parent_path: &str, item: &Yaml, _with_item: Option<Yaml>) -> HarrawAssert {
     let name = hrw_extract(item, "name");
     let key = hrw_extract(&item["assert"], "key");
-    let value = hrw_extract(&item["assert"], "value");
+    let assertion = HarrawAssertion::new(parent_path, &item["assert"], &["key", "on_fail"]);
+    let on_fail = hrw_extract_optional(&item["assert"], "on_fail").map(|v| HarrawFlow::new(&v)).or_else(|| hrw_read_on_error(item));
 
-    HarrawAssert { name, key, value }
+    HarrawAssert { name, key, assertion, on_fail }
   }
 }
 
 #[async_trait]
 impl HarrawRunnable for HarrawAssert {
-  async fn hrw_execute(&self, context: &mut Context, _reports: &mut Reports, _pool: &Pool, config: &HarrawConfig) {
+  async fn hrw_execute(&self, context: &mut Context, reports: &mut Reports, _pool: &Pool, config: &HarrawConfig) -> HarrawFlow {
     if !config.quiet {
-      println!("{:width$} {}={}?", self.name.green(), self.key.cyan().bold(), self.value.magenta(), width = 25);
+      println!("{:width$} {} {}?", self.name.green(), self.key.cyan().bold(), self.assertion.to_string().magenta(), width = 25);
     }
     let interpolator = interpolator::HarrawInterpolator::new(context);
-    let eval = format!("{{{{ {} }}}}", &self.key);
-    let stored = interpolator.hrw_resolve(&eval, true);
-    let assertion = json!(self.value.to_owned());
+    let stored = interpolator.hrw_lookup(&self.key);
+    let result = self.assertion.hrw_evaluate(stored.as_ref()).map_err(|message| format!("{} {}", self.key, message));
 
-    if !stored.eq(&assertion) {
-      panic!("Assertion mismatched: {} != {}", stored, assertion);
-    }
+    hrw_record_check(reports, config, &self.name, result, self.on_fail.unwrap_or(config.on_error))
   }
-}
\ No newline at end of file
+}
@@ -4,7 +4,7 @@ use serde_json::json;
 use yaml_rust::Yaml;
 
 use crate::service::hrw_extract;
-use crate::service::HarrawRunnable;
+use crate::service::{HarrawFlow, HarrawRunnable};
 use crate::benchmark::{Context, Pool, Reports};
 use crate::config::HarrawConfig;
 
@@ -31,10 +31,11 @@ impl HarrawAssign {
 
 #[async_trait]
 impl HarrawRunnable for HarrawAssign {
-  async fn hrw_execute(&self, context: &mut Context, _reports: &mut Reports, _pool: &Pool, config: &HarrawConfig) {
+  async fn hrw_execute(&self, context: &mut Context, _reports: &mut Reports, _pool: &Pool, config: &HarrawConfig) -> HarrawFlow {
     if !config.quiet {
       println!("{:width$} {}={}", self.name.green(), self.key.cyan().bold(), self.value.magenta(), width = 25);
     }
     context.insert(self.key.to_owned(), json!(self.value.to_owned()));
+    HarrawFlow::Continue
   }
 }
\ No newline at end of file
@@ -0,0 +1,161 @@
+use std::time::Instant;
+
+use async_trait::async_trait;
+use colored::*;
+use yaml_rust::Yaml;
+
+use crate::benchmark::{Benchmark, Context, Pool, Reports};
+use crate::config::HarrawConfig;
+use crate::service::{hrw_extract, HarrawFlow, HarrawRunnable, HarrawTransactionReport};
+
+/// A named group of steps, like login → cart → checkout, timed as one transaction.
+pub struct HarrawBlock {
+  name: String,
+  items: Benchmark,
+}
+
+impl HarrawBlock {
+  pub fn hrw_is_that_you(item: &Yaml) -> bool {
+    item["block"].as_vec().is_some() || item["group"].as_vec().is_some()
+  }
+
+  /// The nested plan items of the block.
+  pub fn hrw_items(item: &Yaml) -> &Vec<Yaml> {
+    item["block"].as_vec().or_else(|| item["group"].as_vec()).unwrap()
+  }
+
+  pub fn new(item: &Yaml, items: Benchmark) -> HarrawBlock {
+    let name = hrw_extract(item, "name");
+
+    HarrawBlock {
+      name,
+      items,
+    }
+  }
+}
+
+#[async_trait]
+impl HarrawRunnable for HarrawBlock {
+  async fn hrw_execute(&self, context: &mut Context, reports: &mut Reports, pool: &Pool, config: &HarrawConfig) -> HarrawFlow {
+    let begin = Instant::now();
+    let checks = reports.checks.len();
+    let mut flow = HarrawFlow::Continue;
+
+    for item in self.items.iter() {
+      flow = item.hrw_execute(context, reports, pool, config).await;
+
+      if flow != HarrawFlow::Continue {
+        break;
+      }
+    }
+
+    let duration = begin.elapsed().as_secs_f64() * 1000.0;
+    let passed = flow == HarrawFlow::Continue && reports.checks[checks..].iter().all(|c| c.passed);
+
+    reports.transactions.push(HarrawTransactionReport {
+      name: self.name.clone(),
+      duration,
+      passed,
+    });
+
+    if !config.quiet {
+      let status = if passed { "OK".yellow() } else { "FAILED".red().bold() };
+      println!("{:width$} {} {}", self.name.green(), status, format!("{}ms", duration.round()).cyan(), width = 25);
+    }
+    flow
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::backend::include::hrw_expand_items;
+  use crate::tags::HarrawTags;
+  use crate::service::HarrawTestResponse;
+  use crate::benchmark::{hrw_test_block_on, hrw_test_pool};
+  use serde_json::json;
+
+  fn hrw_run(text: &str, context: &mut Context) -> (HarrawFlow, Reports) {
+    let docs = yaml_rust::YamlLoader::load_from_str(text).unwrap();
+    let mut items = Benchmark::new();
+    hrw_expand_items("benchmark.yml", HarrawBlock::hrw_items(&docs[0]), &mut items, &HarrawTags::new(None, None));
+
+    let block = HarrawBlock::new(&docs[0], items);
+    let mut reports = Reports::default();
+
+    let flow = hrw_test_block_on(block.hrw_execute(context, &mut reports, &hrw_test_pool(), &HarrawConfig::hrw_test(false)));
+    (flow, reports)
+  }
+
+  #[test]
+  fn hrw_expands_nested_steps() {
+    let text = "---\nname: Checkout\ngroup:\n  - name: Cart\n    request:\n      url: /api/cart\n  - name: Pay\n    tags: [payment]\n    request:\n      url: /api/pay\n  - name: Wait\n    delay:\n      seconds: 1";
+    let docs = yaml_rust::YamlLoader::load_from_str(text).unwrap();
+    let tags = HarrawTags::new(Some("checkout"), Some("payment"));
+    let mut items = Benchmark::new();
+
+    assert!(HarrawBlock::hrw_is_that_you(&docs[0]));
+    hrw_expand_items("benchmark.yml", HarrawBlock::hrw_items(&docs[0]), &mut items, &tags.hrw_within_selected());
+
+    assert_eq!(HarrawBlock::new(&docs[0], items).items.len(), 2);
+  }
+
+  #[test]
+  fn hrw_records_a_transaction() {
+    let text = "---\nname: Checkout\nblock:\n  - name: Cart\n    assign:\n      key: cart\n      value: full\n  - name: Cart is full\n    assert:\n      key: cart\n      equals: full";
+    let mut context = Context::new();
+
+    let (flow, reports) = hrw_run(text, &mut context);
+
+    assert_eq!(flow, HarrawFlow::Continue);
+    assert_eq!(reports.transactions.len(), 1);
+    assert_eq!(reports.transactions[0].name, "Checkout");
+    assert!(reports.transactions[0].duration >= 0.0);
+    assert!(reports.transactions[0].passed);
+  }
+
+  #[test]
+  fn hrw_fails_with_a_failed_check() {
+    let text = "---\nname: Checkout\nblock:\n  - name: Cart is full\n    assert:\n      key: cart\n      equals: full\n  - name: Pay\n    assign:\n      key: paid\n      value: yes";
+    let mut context = Context::new();
+    context.insert("cart".to_string(), json!("empty"));
+
+    let (flow, reports) = hrw_run(text, &mut context);
+
+    assert_eq!(flow, HarrawFlow::Continue);
+    assert!(!reports.transactions[0].passed);
+    assert!(context.contains_key("paid"));
+  }
+
+  #[test]
+  fn hrw_stops_on_abort_iteration() {
+    let text = "---\nname: Checkout\nblock:\n  - name: Cart is full\n    assert:\n      key: cart\n      equals: full\n      on_fail: abort_iteration\n  - name: Pay\n    assign:\n      key: paid\n      value: yes";
+    let mut context = Context::new();
+    context.insert("cart".to_string(), json!("empty"));
+
+    let (flow, reports) = hrw_run(text, &mut context);
+
+    assert_eq!(flow, HarrawFlow::AbortIteration);
+    assert!(!reports.transactions[0].passed);
+    assert!(!context.contains_key("paid"));
+  }
+
+  #[test]
+  fn hrw_accepted_statuses_pass() {
+    let block = |status: &str| {
+      let text = format!("---\nname: Lookup\nblock:\n  - name: Missing user\n    status: 404\n    check:\n      status: {}", status);
+      let docs = yaml_rust::YamlLoader::load_from_str(&text).unwrap();
+      let items: Benchmark = vec![Box::new(HarrawTestResponse::new(&docs[0]["block"][0]))];
+      HarrawBlock::new(&docs[0], items)
+    };
+    let config = HarrawConfig::hrw_test(false);
+
+    let mut reports = Reports::default();
+    hrw_test_block_on(block("404").hrw_execute(&mut Context::new(), &mut reports, &hrw_test_pool(), &config));
+    assert!(reports.transactions[0].passed);
+
+    let mut reports = Reports::default();
+    hrw_test_block_on(block("200").hrw_execute(&mut Context::new(), &mut reports, &hrw_test_pool(), &config));
+    assert!(!reports.transactions[0].passed);
+  }
+}
@@ -0,0 +1,210 @@
+use std::collections::HashMap;
+
+use serde_json::{json, Value};
+use yaml_rust::Yaml;
+
+use crate::assertion::HarrawAssertion;
+use crate::benchmark::Reports;
+use crate::config::HarrawConfig;
+use crate::interpolator::hrw_json_pointer;
+use crate::service::{hrw_extract_optional, hrw_read_on_error, hrw_record_check, HarrawFlow};
+
+#[derive(Clone)]
+enum HarrawCheckTarget {
+  Status,
+  Header(String),
+  BodyPath(String),
+  Body,
+  JsonBody,
+  ResponseTime,
+}
+
+#[derive(Clone)]
+struct HarrawCheck {
+  label: String,
+  target: HarrawCheckTarget,
+  assertion: HarrawAssertion,
+}
+
+/// What checks and extractions are evaluated against, gathered from the response of a request.
+pub struct HarrawCheckedResponse<'a> {
+  pub status: u16,
+  pub headers: &'a HashMap<String, String>,
+  pub body: &'a str,
+  pub duration_ms: f64,
+}
+
+/// The `check:` block of a request item.
+#[derive(Clone)]
+pub struct HarrawChecks {
+  checks: Vec<HarrawCheck>,
+  on_fail: Option<HarrawFlow>,
+}
+
+impl HarrawChecks {
+  pub fn new(parent_path: &str, item: &Yaml) -> Option<HarrawChecks> {
+    let hash = item["check"].as_hash()?;
+    let mut checks = Vec::new();
+
+    for (key, node) in hash.iter() {
+      let key = key.as_str().expect("`check` keys must be strings");
+
+      match key {
+        "on_fail" => {}
+        "status" => checks.push(HarrawCheck {
+          label: "status".to_string(),
+          target: HarrawCheckTarget::Status,
+          assertion: HarrawAssertion::new(parent_path, node, &[]),
+        }),
+        "response_time" => checks.push(HarrawCheck {
+          label: "response time".to_string(),
+          target: HarrawCheckTarget::ResponseTime,
+          assertion: HarrawAssertion::new(parent_path, node, &[]),
+        }),
+        "body_matches" | "body_contains" => checks.push(HarrawCheck {
+          label: key.replace('_', " "),
+          target: HarrawCheckTarget::Body,
+          assertion: HarrawAssertion::hrw_from_operator(parent_path, &key[5..], node),
+        }),
+        "schema" => checks.push(HarrawCheck {
+          label: "body schema".to_string(),
+          target: HarrawCheckTarget::JsonBody,
+          assertion: HarrawAssertion::hrw_from_operator(parent_path, key, node),
+        }),
+        "headers" | "body" => {
+          let entries = node.as_hash().unwrap_or_else(|| panic!("`check.{}` needs to be a hash", key));
+
+          for (name, node) in entries.iter() {
+            let name = name.as_str().unwrap_or_else(|| panic!("`check.{}` keys must be strings", key)).to_string();
+            let (label, target) = if key == "headers" {
+              (format!("header {name}"), HarrawCheckTarget::Header(name.to_lowercase()))
+            } else {
+              (format!("body {name}"), HarrawCheckTarget::BodyPath(name))
+            };
+
+            checks.push(HarrawCheck {
+              label,
+              target,
+              assertion: HarrawAssertion::new(parent_path, node, &[]),
+            });
+          }
+        }
+        _ => panic!("Unknown check `{}`", key),
+      }
+    }
+
+    let on_fail = hrw_extract_optional(&item["check"], "on_fail").map(|v| HarrawFlow::new(&v)).or_else(|| hrw_read_on_error(item));
+
+    Some(HarrawChecks { checks, on_fail })
+  }
+
+  pub fn hrw_needs_body(&self) -> bool {
+    self.checks.iter().any(|c| matches!(c.target, HarrawCheckTarget::Body | HarrawCheckTarget::JsonBody | HarrawCheckTarget::BodyPath(_)))
+  }
+
+  /// Evaluates every check against the response, or fails all of them when there was none.
+  pub fn hrw_evaluate(&self, name: &str, response: Option<&HarrawCheckedResponse>, reports: &mut Reports, config: &HarrawConfig) -> HarrawFlow {
+    let json_body: Option<Value> = response.filter(|_| self.hrw_needs_body()).and_then(|r| serde_json::from_str(r.body).ok());
+    let mut flow = HarrawFlow::Continue;
+
+    for check in self.checks.iter() {
+      let check_name = format!("{} {}", name, check.label);
+
+      let result = match response {
+        None => Err("no response".to_string()),
+        Some(response) => {
+          let actual = match &check.target {
+            HarrawCheckTarget::Status => Some(json!(response.status)),
+            HarrawCheckTarget::Header(header) => response.headers.get(header).map(|v| json!(v)),
+            HarrawCheckTarget::Body => Some(json!(response.body)),
+            HarrawCheckTarget::JsonBody => json_body.clone(),
+            HarrawCheckTarget::BodyPath(path) => json_body.as_ref().and_then(|body| body.pointer(&hrw_json_pointer(path))).cloned(),
+            HarrawCheckTarget::ResponseTime => Some(json!(response.duration_ms)),
+          };
+
+          check.assertion.hrw_evaluate(actual.as_ref()).map_err(|message| format!("{} {}", check.label, message))
+        }
+      };
+
+      flow = flow.max(hrw_record_check(reports, config, &check_name, result, self.on_fail.unwrap_or(config.on_error)));
+    }
+    flow
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn hrw_checks(text: &str) -> HarrawChecks {
+    let docs = yaml_rust::YamlLoader::load_from_str(text).unwrap();
+    HarrawChecks::new("benchmark.yml", &docs[0]).unwrap()
+  }
+
+  #[test]
+  fn hrw_evaluates_every_check() {
+    let checks = hrw_checks("---\ncheck:\n  status: { in: [200, 201] }\n  headers:\n    Content-Type: { contains: json }\n  body:\n    user.id: 12\n    \"[0]\": { not_exists: true }\n  body_contains: Mary\n  response_time: { lt: 500 }");
+    let headers = HashMap::from([("content-type".to_string(), "application/json".to_string())]);
+    let response = HarrawCheckedResponse {
+      status: 201,
+      headers: &headers,
+      body: "{\"user\": {\"id\": 12, \"name\": \"Mary\"}}",
+      duration_ms: 12.0,
+    };
+    let mut reports = Reports::default();
+
+    let flow = checks.hrw_evaluate("foo", Some(&response), &mut reports, &HarrawConfig::hrw_test(false));
+
+    assert_eq!(flow, HarrawFlow::Continue);
+    assert_eq!(reports.checks.len(), 6);
+    assert!(reports.checks.iter().all(|c| c.passed));
+  }
+
+  #[test]
+  fn hrw_missing_response_fails_every_check() {
+    let checks = hrw_checks("---\ncheck:\n  status: 200\n  body_matches: \"^ok$\"\n  on_fail: abort_iteration");
+    let mut reports = Reports::default();
+
+    let flow = checks.hrw_evaluate("foo", None, &mut reports, &HarrawConfig::hrw_test(false));
+
+    assert_eq!(flow, HarrawFlow::AbortIteration);
+    assert_eq!(reports.checks.iter().filter(|c| !c.passed).count(), 2);
+    assert_eq!(reports.checks[0].name, "foo status");
+  }
+
+  #[test]
+  fn hrw_step_on_error_applies_to_checks() {
+    let checks = hrw_checks("---\non_error: skip_rest_of_iteration\ncheck:\n  status: 200");
+    let mut reports = Reports::default();
+
+    assert_eq!(checks.hrw_evaluate("foo", None, &mut reports, &HarrawConfig::hrw_test(false)), HarrawFlow::AbortIteration);
+
+    let checks = hrw_checks("---\non_error: abort_run\ncheck:\n  status: 200\n  on_fail: continue");
+
+    assert_eq!(checks.hrw_evaluate("foo", None, &mut reports, &HarrawConfig::hrw_test(false)), HarrawFlow::Continue);
+  }
+
+  #[test]
+  fn hrw_schema_validates_the_json_body() {
+    let checks = hrw_checks("---\ncheck:\n  schema: ./fixtures/user.schema.json");
+    let headers = HashMap::new();
+    let response = HarrawCheckedResponse {
+      status: 200,
+      headers: &headers,
+      body: "{\"id\": \"12\", \"name\": \"Mary\"}",
+      duration_ms: 12.0,
+    };
+    let mut reports = Reports::default();
+
+    checks.hrw_evaluate("foo", Some(&response), &mut reports, &HarrawConfig::hrw_test(false));
+
+    assert_eq!(reports.checks[0].name, "foo body schema");
+    assert!(!reports.checks[0].passed);
+  }
+
+  #[test]
+  #[should_panic(expected = "Unknown check `cookies`")]
+  fn hrw_unknown_check() {
+    hrw_checks("---\ncheck:\n  cookies: 1");
+  }
+}
@@ -4,7 +4,7 @@ use tokio::time::sleep;
 use yaml_rust::Yaml;
 
 use crate::service::hrw_extract;
-use crate::service::HarrawRunnable;
+use crate::service::{HarrawFlow, HarrawRunnable};
 use crate::benchmark::{Context, Pool, Reports};
 use crate::config::HarrawConfig;
 
@@ -36,11 +36,12 @@ impl HarrawDelay {
   
   #[async_trait]
   impl HarrawRunnable for HarrawDelay {
-    async fn hrw_execute(&self, _context: &mut Context, _reports: &mut Reports, _pool: &Pool, config: &HarrawConfig) {
+    async fn hrw_execute(&self, _context: &mut Context, _reports: &mut Reports, _pool: &Pool, config: &HarrawConfig) -> HarrawFlow {
       sleep(Duration::from_secs(self.seconds)).await;
   
       if !config.quiet {
         println!("{:width$} {}{}", self.name.green(), self.seconds.to_string().cyan().bold(), "s".magenta(), width = 25);
       }
+      HarrawFlow::Continue
     }
   }
\ No newline at end of file
@@ -8,7 +8,7 @@ use yaml_rust::Yaml;
 use crate::interpolator;
 use crate::config::*;
 use crate::benchmark::{Context, Pool, Reports};
-use crate::service::HarrawRunnable;
+use crate::service::{hrw_read_on_error, HarrawFlow, HarrawRunnable};
 
 use super::hrw_extract;
 use super::hrw_extract_optional;
@@ -18,7 +18,8 @@ use super::hrw_extract_optional;
 pub struct HarrawExec {
     name: String,
     command: String,
-    pub assign: Option<String>
+    pub assign: Option<String>,
+    pub on_error: Option<HarrawFlow>,
 }
 
 impl HarrawExec {
@@ -30,24 +31,33 @@ impl HarrawExec {
         let name = hrw_extract(item, "name");
         let command = hrw_extract(&item["exec"], "command");
         let assign = hrw_extract_optional(item, "assign");
+        let on_error = hrw_read_on_error(item);
     
-        HarrawExec { name, command, assign, }
+        HarrawExec { name, command, assign, on_error }
       }
 }
 
 
 #[async_trait]
 impl HarrawRunnable for HarrawExec {
-  async fn hrw_execute(&self, context: &mut Context, _reports: &mut Reports, _pool: &Pool, config: &HarrawConfig) {
+  async fn hrw_execute(&self, context: &mut Context, _reports: &mut Reports, _pool: &Pool, config: &HarrawConfig) -> HarrawFlow {
     if !config.quiet {
       println!("{:width$} {}", self.name.green(), self.command.cyan().bold(), width = 25);
     }
 
     let final_command = interpolator::HarrawInterpolator::new(context).hrw_resolve(&self.command, !config.relaxed_interpolations);
 
-    let args = vec!["bash", "-c", "--", final_command.as_str()];
+    let args = ["bash", "-c", "--", final_command.as_str()];
 
-    let execution = Command::new(args[0]).args(&args[1..]).output().expect("Couldn't run it");
+    let execution = match Command::new(args[0]).args(&args[1..]).output() {
+      Ok(execution) => execution,
+      Err(e) => {
+        if !config.quiet {
+          println!("{:width$} {} {}", self.name.green(), "Couldn't run it".red().bold(), e.to_string().red(), width = 25);
+        }
+        return self.on_error.unwrap_or(config.on_error);
+      }
+    };
 
     let output: String = String::from_utf8_lossy(&execution.stdout).into();
     let output = output.trim_end().to_string();
@@ -55,5 +65,13 @@ impl HarrawRunnable for HarrawExec {
     if let Some(ref key) = self.assign {
       context.insert(key.to_owned(), json!(output));
     }
+
+    if !execution.status.success() {
+      if !config.quiet {
+        println!("{:width$} {} {}", self.name.green(), "FAILED".red().bold(), execution.status.to_string().red(), width = 25);
+      }
+      return self.on_error.unwrap_or(config.on_error);
+    }
+    HarrawFlow::Continue
   }
 }
\ No newline at end of file
@@ -0,0 +1,337 @@
+use regex::Regex;
+use scraper::{Html, Selector};
+use serde_json::{json, Value};
+use serde_json_path::JsonPath;
+use sxd_xpath::{Context as XPathContext, Factory, XPath};
+use yaml_rust::Yaml;
+
+use crate::benchmark::{Context, Reports};
+use crate::config::HarrawConfig;
+use crate::service::check::HarrawCheckedResponse;
+use crate::service::{hrw_extract_optional, hrw_read_on_error, hrw_record_check, hrw_yaml_to_json, HarrawFlow};
+
+#[derive(Clone)]
+enum HarrawExtractSource {
+  JsonPath(JsonPath),
+  Regex { regex: Regex, group: String, all: bool },
+  Header(String),
+  Css { selector: Selector, attribute: Option<String> },
+  XPath { xpath: String, namespaces: Vec<(String, String)> },
+}
+
+/// Compiles an xpath expression. sxd_xpath's compiled expressions can't cross threads,
+/// so plans only keep the expression and compile it again for every response.
+fn hrw_compile_xpath(expression: &str) -> XPath {
+  match Factory::new().build(expression) {
+    Ok(Some(xpath)) => xpath,
+    Ok(None) => panic!("Empty xpath"),
+    Err(e) => panic!("Invalid xpath `{}`: {}", expression, e),
+  }
+}
+
+#[derive(Clone)]
+struct HarrawExtraction {
+  key: String,
+  source: HarrawExtractSource,
+  default: Option<Value>,
+}
+
+/// The `extract:` block of a request item, writing response values into the context.
+#[derive(Clone)]
+pub struct HarrawExtractions {
+  extractions: Vec<HarrawExtraction>,
+  on_fail: Option<HarrawFlow>,
+}
+
+impl HarrawExtractions {
+  pub fn new(item: &Yaml) -> Option<HarrawExtractions> {
+    let hash = item["extract"].as_hash()?;
+    let mut extractions = Vec::new();
+
+    for (key, node) in hash.iter() {
+      let key = key.as_str().expect("`extract` keys must be strings");
+
+      if key == "on_fail" {
+        continue;
+      }
+
+      let source = if let Some(path) = node["jsonpath"].as_str() {
+        HarrawExtractSource::JsonPath(JsonPath::parse(path).unwrap_or_else(|e| panic!("Invalid jsonpath `{}`: {}", path, e)))
+      } else if let Some(pattern) = node["regex"].as_str() {
+        let regex = Regex::new(pattern).unwrap_or_else(|e| panic!("Invalid regex `{}`: {}", pattern, e));
+        let group = match (node["group"].as_i64(), node["group"].as_str()) {
+          (Some(index), _) => index.to_string(),
+          (None, Some(name)) => name.to_string(),
+          (None, None) if regex.captures_len() > 1 => "1".to_string(),
+          (None, None) => "0".to_string(),
+        };
+
+        match group.parse::<usize>() {
+          Ok(index) if index >= regex.captures_len() => panic!("Regex `{}` has no capture group {}", pattern, index),
+          Err(_) if !regex.capture_names().flatten().any(|n| n == group) => panic!("Regex `{}` has no capture group named `{}`", pattern, group),
+          _ => {}
+        }
+
+        let all = node["all"].as_bool().unwrap_or(false);
+
+        HarrawExtractSource::Regex { regex, group, all }
+      } else if let Some(header) = node["header"].as_str() {
+        HarrawExtractSource::Header(header.to_lowercase())
+      } else if let Some(css) = node["css"].as_str() {
+        let selector = Selector::parse(css).unwrap_or_else(|e| panic!("Invalid css selector `{}`: {}", css, e));
+        let attribute = node["attribute"].as_str().map(|a| a.to_string());
+
+        HarrawExtractSource::Css { selector, attribute }
+      } else if let Some(expression) = node["xpath"].as_str() {
+        // Only compiled to reject invalid expressions while loading the plan
+        hrw_compile_xpath(expression);
+        let xpath = expression.to_string();
+        let namespaces = match node["namespaces"].as_hash() {
+          Some(hash) => hash.iter().map(|(prefix, uri)| (prefix.as_str().unwrap().to_string(), uri.as_str().expect("xpath namespaces must be strings").to_string())).collect(),
+          None => Vec::new(),
+        };
+
+        HarrawExtractSource::XPath { xpath, namespaces }
+      } else {
+        panic!("`extract.{}` needs a jsonpath, regex, header, css or xpath", key);
+      };
+
+      let default = if node["default"].is_badvalue() {
+        None
+      } else {
+        Some(hrw_yaml_to_json(node["default"].clone()))
+      };
+
+      extractions.push(HarrawExtraction {
+        key: key.to_string(),
+        source,
+        default,
+      });
+    }
+
+    let on_fail = hrw_extract_optional(&item["extract"], "on_fail").map(|v| HarrawFlow::new(&v)).or_else(|| hrw_read_on_error(item));
+
+    Some(HarrawExtractions { extractions, on_fail })
+  }
+
+  pub fn hrw_needs_body(&self) -> bool {
+    self.extractions.iter().any(|e| !matches!(e.source, HarrawExtractSource::Header(_)))
+  }
+
+  /// Stores every extracted value in the context. Extractions without a `default`
+  /// are recorded as checks, so a value that cannot be found counts as a failure.
+  pub fn hrw_extract(&self, name: &str, response: Option<&HarrawCheckedResponse>, context: &mut Context, reports: &mut Reports, config: &HarrawConfig) -> HarrawFlow {
+    let json_body: Option<Value> = response.filter(|_| self.hrw_needs_body()).and_then(|r| serde_json::from_str(r.body).ok());
+    let mut flow = HarrawFlow::Continue;
+
+    for extraction in self.extractions.iter() {
+      let value = response.and_then(|response| extraction.source.hrw_find(response, json_body.as_ref()));
+
+      let result = match (value, &extraction.default) {
+        (Some(value), _) => {
+          context.insert(extraction.key.to_owned(), value);
+          Ok(())
+        }
+        (None, Some(default)) => {
+          context.insert(extraction.key.to_owned(), default.to_owned());
+          continue;
+        }
+        (None, None) => Err(format!("nothing to extract into `{}`", extraction.key)),
+      };
+
+      let check_name = format!("{} extract {}", name, extraction.key);
+      flow = flow.max(hrw_record_check(reports, config, &check_name, result, self.on_fail.unwrap_or(config.on_error)));
+    }
+    flow
+  }
+}
+
+impl HarrawExtractSource {
+  fn hrw_find(&self, response: &HarrawCheckedResponse, json_body: Option<&Value>) -> Option<Value> {
+    match self {
+      HarrawExtractSource::JsonPath(path) => {
+        let mut nodes: Vec<Value> = path.query(json_body?).all().into_iter().cloned().collect();
+
+        match nodes.len() {
+          0 => None,
+          1 => nodes.pop(),
+          _ => Some(json!(nodes)),
+        }
+      }
+      HarrawExtractSource::Regex { regex, group, all } => {
+        let mut found = regex.captures_iter(response.body).filter_map(|caps| {
+          let capture = match group.parse::<usize>() {
+            Ok(index) => caps.get(index),
+            Err(_) => caps.name(group),
+          };
+          capture.map(|c| json!(c.as_str()))
+        });
+
+        if *all {
+          let found: Vec<Value> = found.collect();
+          (!found.is_empty()).then(|| json!(found))
+        } else {
+          found.next()
+        }
+      }
+      HarrawExtractSource::Header(header) => response.headers.get(header).map(|v| json!(v)),
+      HarrawExtractSource::Css { selector, attribute } => {
+        let document = Html::parse_document(response.body);
+        let found: Vec<String> = document
+          .select(selector)
+          .filter_map(|element| match attribute {
+            Some(attribute) => element.value().attr(attribute).map(|v| v.to_string()),
+            None => Some(element.text().collect::<String>().trim().to_string()),
+          })
+          .collect();
+
+        hrw_one_or_many(found)
+      }
+      HarrawExtractSource::XPath { xpath, namespaces } => {
+        let package = sxd_document::parser::parse(response.body).ok()?;
+        let document = package.as_document();
+        let mut context = XPathContext::new();
+
+        for (prefix, uri) in namespaces.iter() {
+          context.set_namespace(prefix, uri);
+        }
+
+        match hrw_compile_xpath(xpath).evaluate(&context, document.root()).ok()? {
+          sxd_xpath::Value::Nodeset(nodes) => hrw_one_or_many(nodes.document_order().iter().map(|n| n.string_value()).collect()),
+          sxd_xpath::Value::Boolean(v) => Some(json!(v)),
+          sxd_xpath::Value::Number(v) => Some(json!(v)),
+          sxd_xpath::Value::String(v) => Some(json!(v)),
+        }
+      }
+    }
+  }
+}
+
+fn hrw_one_or_many(mut found: Vec<String>) -> Option<Value> {
+  match found.len() {
+    0 => None,
+    1 => found.pop().map(|v| json!(v)),
+    _ => Some(json!(found)),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::collections::HashMap;
+
+  fn hrw_extractions(text: &str) -> HarrawExtractions {
+    let docs = yaml_rust::YamlLoader::load_from_str(text).unwrap();
+    HarrawExtractions::new(&docs[0]).unwrap()
+  }
+
+  #[test]
+  fn hrw_extracts_into_the_context() {
+    let extractions = hrw_extractions(
+      "---\nextract:\n  first: { jsonpath: \"$.results[0].id\" }\n  cheap: { jsonpath: \"$.results[?@.price < 10].id\" }\n  token: { regex: 'token=(\\w+)' }\n  named: { regex: 'id=(?P<id>\\d+)', group: id, all: true }\n  session: { header: X-Session }",
+    );
+    let headers = HashMap::from([("x-session".to_string(), "abc".to_string())]);
+    let body = "{\"results\": [{\"id\": 1, \"price\": 5}, {\"id\": 2, \"price\": 50}, {\"id\": 3, \"price\": 2}], \"next\": \"token=xyz id=1 id=3\"}";
+    let response = HarrawCheckedResponse {
+      status: 200,
+      headers: &headers,
+      body,
+      duration_ms: 1.0,
+    };
+    let mut context = Context::new();
+    let mut reports = Reports::default();
+
+    let flow = extractions.hrw_extract("foo", Some(&response), &mut context, &mut reports, &HarrawConfig::hrw_test(false));
+
+    assert_eq!(flow, HarrawFlow::Continue);
+    assert_eq!(context["first"], json!(1));
+    assert_eq!(context["cheap"], json!([1, 3]));
+    assert_eq!(context["token"], json!("xyz"));
+    assert_eq!(context["named"], json!(["1", "3"]));
+    assert_eq!(context["session"], json!("abc"));
+    assert!(reports.checks.iter().all(|c| c.passed));
+  }
+
+  #[test]
+  fn hrw_uses_defaults_or_fails_when_nothing_matches() {
+    let extractions = hrw_extractions("---\nextract:\n  token: { regex: 'token=(\\w+)', default: none }\n  id: { jsonpath: \"$.id\" }\n  on_fail: abort_iteration");
+    let headers = HashMap::new();
+    let response = HarrawCheckedResponse {
+      status: 200,
+      headers: &headers,
+      body: "not json",
+      duration_ms: 1.0,
+    };
+    let mut context = Context::new();
+    let mut reports = Reports::default();
+
+    let flow = extractions.hrw_extract("foo", Some(&response), &mut context, &mut reports, &HarrawConfig::hrw_test(false));
+
+    assert_eq!(flow, HarrawFlow::AbortIteration);
+    assert_eq!(context["token"], json!("none"));
+    assert!(!context.contains_key("id"));
+    assert_eq!(reports.checks.len(), 1);
+    assert_eq!(reports.checks[0].name, "foo extract id");
+  }
+
+  #[test]
+  fn hrw_extracts_from_html_with_css_selectors() {
+    let extractions = hrw_extractions("---\nextract:\n  csrf: { css: \"input[name=csrf]\", attribute: value }\n  items: { css: \"li.item\" }\n  missing: { css: \"form#nope\" }");
+    let headers = HashMap::new();
+    let body = "<html><body><form><input type=\"hidden\" name=\"csrf\" value=\"t0k3n\"></form><ul><li class=\"item\"> a </li><li class=\"item\">b</li></ul></body></html>";
+    let response = HarrawCheckedResponse {
+      status: 200,
+      headers: &headers,
+      body,
+      duration_ms: 1.0,
+    };
+    let mut context = Context::new();
+    let mut reports = Reports::default();
+
+    extractions.hrw_extract("foo", Some(&response), &mut context, &mut reports, &HarrawConfig::hrw_test(false));
+
+    assert_eq!(context["csrf"], json!("t0k3n"));
+    assert_eq!(context["items"], json!(["a", "b"]));
+    assert_eq!(reports.checks.iter().filter(|c| !c.passed).count(), 1);
+  }
+
+  #[test]
+  fn hrw_extracts_from_xml_with_xpath() {
+    let extractions = hrw_extractions(
+      "---\nextract:\n  session: { xpath: \"//s:Body/m:LoginResponse/m:Session\", namespaces: { s: \"http://schemas.xmlsoap.org/soap/envelope/\", m: \"urn:auth\" } }\n  count: { xpath: \"count(//*[local-name()='Role'])\" }",
+    );
+    let headers = HashMap::new();
+    let body = "<s:Envelope xmlns:s=\"http://schemas.xmlsoap.org/soap/envelope/\"><s:Body><LoginResponse xmlns=\"urn:auth\"><Session>abc</Session><Role>a</Role><Role>b</Role></LoginResponse></s:Body></s:Envelope>";
+    let response = HarrawCheckedResponse {
+      status: 200,
+      headers: &headers,
+      body,
+      duration_ms: 1.0,
+    };
+    let mut context = Context::new();
+    let mut reports = Reports::default();
+
+    extractions.hrw_extract("foo", Some(&response), &mut context, &mut reports, &HarrawConfig::hrw_test(false));
+
+    assert_eq!(context["session"], json!("abc"));
+    assert_eq!(context["count"], json!(2.0));
+  }
+
+  #[test]
+  #[should_panic(expected = "Invalid xpath `//Session[`")]
+  fn hrw_rejects_invalid_xpath_when_loading() {
+    hrw_extractions("---\nextract:\n  session: { xpath: \"//Session[\" }");
+  }
+
+  #[test]
+  #[should_panic(expected = "has no capture group named `token`")]
+  fn hrw_rejects_unknown_regex_group_names() {
+    hrw_extractions("---\nextract:\n  token: { regex: 'id=(?P<id>\\d+)', group: token }");
+  }
+
+  #[test]
+  #[should_panic(expected = "has no capture group 2")]
+  fn hrw_rejects_out_of_range_regex_groups() {
+    hrw_extractions("---\nextract:\n  token: { regex: 'id=(\\d+)', group: 2 }");
+  }
+}
@@ -0,0 +1,129 @@
+use async_trait::async_trait;
+use colored::*;
+use rand::seq::SliceRandom;
+use serde_json::{json, Value};
+use yaml_rust::Yaml;
+
+use crate::benchmark::{Context, Pool, Reports};
+use crate::config::HarrawConfig;
+use crate::interpolator::{hrw_single_interpolation, hrw_value_to_string, HarrawInterpolator};
+use crate::random::hrw_with_rng;
+use crate::service::request::HarrawRequest;
+use crate::service::{HarrawFlow, HarrawRunnable};
+
+/// A request repeated for every item of a list only known while the iteration runs,
+/// like `with_items: "{{ search.body.results }}"`.
+#[derive(Clone)]
+pub struct HarrawForEach {
+  expression: String,
+  shuffle: bool,
+  pick: Option<usize>,
+  request: HarrawRequest,
+}
+
+impl HarrawForEach {
+  pub fn hrw_is_that_you(item: &Yaml) -> bool {
+    item["request"].as_hash().is_some() && item["with_items"].as_str().is_some()
+  }
+
+  pub fn new(parent_path: &str, item: &Yaml) -> HarrawForEach {
+    let with_items = item["with_items"].as_str().unwrap();
+    let expression = match hrw_single_interpolation(with_items) {
+      Some(expression) => expression.to_string(),
+      None => panic!("`with_items` needs to be a list or a single interpolation like \"{{{{ foo.body }}}}\", but was `{}`", with_items),
+    };
+    let pick = item["pick"].as_i64().map(|value| usize::try_from(value).unwrap_or_else(|_| panic!("pick option should not be negative, but was {}", value)));
+
+    HarrawForEach {
+      expression,
+      shuffle: item["shuffle"].as_bool().unwrap_or(false),
+      pick,
+      request: HarrawRequest::new(parent_path, item, None, None),
+    }
+  }
+
+  fn hrw_resolve_items(&self, context: &Context, config: &HarrawConfig) -> Vec<Value> {
+    let interpolator = HarrawInterpolator::new(context);
+
+    let error = match interpolator.hrw_evaluate(&self.expression) {
+      Ok(Value::Array(items)) => return items,
+      Ok(other) => format!("`with_items` needs a list, but `{}` was `{}`", self.expression, hrw_value_to_string(&other)),
+      Err(error) => error.to_string(),
+    };
+
+    if !config.relaxed_interpolations {
+      panic!("{}", error);
+    }
+    eprintln!("{} {}", "WARNING!".yellow().bold(), error);
+    Vec::new()
+  }
+}
+
+#[async_trait]
+impl HarrawRunnable for HarrawForEach {
+  async fn hrw_execute(&self, context: &mut Context, reports: &mut Reports, pool: &Pool, config: &HarrawConfig) -> HarrawFlow {
+    let mut items = self.hrw_resolve_items(context, config);
+
+    if self.shuffle {
+      hrw_with_rng(|rng| items.shuffle(rng));
+    }
+
+    if let Some(pick) = self.pick {
+      items.truncate(pick);
+    }
+
+    for (index, item) in items.into_iter().enumerate() {
+      context.insert("item".to_string(), item);
+      context.insert("index".to_string(), json!(index));
+
+      let flow = self.request.hrw_execute(context, reports, pool, config).await;
+
+      if flow != HarrawFlow::Continue {
+        return flow;
+      }
+    }
+    HarrawFlow::Continue
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn hrw_for_each(text: &str) -> HarrawForEach {
+    let docs = yaml_rust::YamlLoader::load_from_str(text).unwrap();
+    HarrawForEach::new("benchmark.yml", &docs[0])
+  }
+
+  #[test]
+  fn hrw_resolves_items_from_the_context() {
+    let for_each = hrw_for_each("---\nname: foobar\nrequest:\n  url: /api/{{ item.id }}\nwith_items: \"{{ search.body.results }}\"");
+    let mut context = Context::new();
+    context.insert("search".to_string(), json!({"body": {"results": [{"id": 1}, {"id": 2}]}}));
+
+    assert_eq!(for_each.hrw_resolve_items(&context, &HarrawConfig::hrw_test(false)), vec![json!({"id": 1}), json!({"id": 2})]);
+  }
+
+  #[test]
+  fn hrw_relaxed_missing_items() {
+    let for_each = hrw_for_each("---\nname: foobar\nrequest:\n  url: /api/{{ item }}\nwith_items: \"{{ search.body }}\"");
+
+    assert!(for_each.hrw_resolve_items(&Context::new(), &HarrawConfig::hrw_test(true)).is_empty());
+  }
+
+  #[test]
+  #[should_panic(expected = "`with_items` needs a list, but `search` was `foo`")]
+  fn hrw_items_need_to_be_a_list() {
+    let for_each = hrw_for_each("---\nname: foobar\nrequest:\n  url: /api/{{ item }}\nwith_items: \"{{ search }}\"");
+    let mut context = Context::new();
+    context.insert("search".to_string(), json!("foo"));
+
+    for_each.hrw_resolve_items(&context, &HarrawConfig::hrw_test(false));
+  }
+
+  #[test]
+  #[should_panic(expected = "`with_items` needs to be a list or a single interpolation")]
+  fn hrw_items_need_a_single_interpolation() {
+    hrw_for_each("---\nname: foobar\nrequest:\n  url: /api/{{ item }}\nwith_items: \"{{ a }},{{ b }}\"");
+  }
+}
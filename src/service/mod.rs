@@ -1,10 +1,21 @@
 pub mod assert;
 pub mod assign;
+pub mod block;
+pub mod check;
 pub mod delay;
 pub mod exec;
+pub mod extract;
+pub mod for_each;
+pub mod parallel;
 pub mod request;
+pub mod scope;
+pub mod share;
+pub mod until;
+pub mod when;
 
 use async_trait::async_trait;
+use colored::*;
+use serde_json::{json, Map, Value};
 use yaml_rust::Yaml;
 
 
@@ -18,7 +29,31 @@ use std::fmt;
 
 #[async_trait]
 pub trait HarrawRunnable {
-  async fn hrw_execute(&self, context: &mut Context, reports: &mut Reports, pool: &Pool, config: &HarrawConfig);
+  async fn hrw_execute(&self, context: &mut Context, reports: &mut Reports, pool: &Pool, config: &HarrawConfig) -> HarrawFlow;
+}
+
+/// What the iteration should do after an item has been executed, from the mildest to the most severe.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum HarrawFlow {
+  Continue,
+  AbortIteration,
+  AbortRun,
+}
+
+impl HarrawFlow {
+  pub fn new(value: &str) -> HarrawFlow {
+    match value {
+      "continue" => HarrawFlow::Continue,
+      "abort_iteration" | "skip_rest_of_iteration" => HarrawFlow::AbortIteration,
+      "abort_run" => HarrawFlow::AbortRun,
+      _ => panic!("Unknown failure policy `{}`, expected continue, skip_rest_of_iteration or abort_run", value),
+    }
+  }
+}
+
+/// The `on_error` policy of a plan item, when it has one. Steps without one follow the plan wide policy.
+pub fn hrw_read_on_error(item: &Yaml) -> Option<HarrawFlow> {
+  hrw_extract_optional(item, "on_error").map(|value| HarrawFlow::new(&value))
 }
 
 #[derive(Clone)]
@@ -28,6 +63,29 @@ pub struct HarrawReport {
   pub status: u16,
 }
 
+#[derive(Clone)]
+pub struct HarrawCheckReport {
+  pub name: String,
+  pub passed: bool,
+}
+
+/// The elapsed time of a step spanning several requests, like a block or a polling loop.
+#[derive(Clone)]
+pub struct HarrawTransactionReport {
+  pub name: String,
+  pub duration: f64,
+  pub passed: bool,
+}
+
+/// Everything recorded during one iteration.
+#[derive(Clone, Default)]
+pub struct HarrawReports {
+  pub requests: Vec<HarrawReport>,
+  pub checks: Vec<HarrawCheckReport>,
+  pub skipped: Vec<String>,
+  pub transactions: Vec<HarrawTransactionReport>,
+}
+
 impl fmt::Debug for HarrawReport {
   fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
     write!(f, "\n- name: {}\n  duration: {}\n", self.name, self.duration)
@@ -40,6 +98,69 @@ impl fmt::Display for HarrawReport {
   }
 }
 
+/// Records the outcome of a check and tells the iteration how to carry on.
+pub fn hrw_record_check(reports: &mut Reports, config: &HarrawConfig, name: &str, result: Result<(), String>, on_fail: HarrawFlow) -> HarrawFlow {
+  reports.checks.push(HarrawCheckReport {
+    name: name.to_string(),
+    passed: result.is_ok(),
+  });
+
+  match result {
+    Ok(()) => HarrawFlow::Continue,
+    Err(message) => {
+      if !config.quiet {
+        println!("{:width$} {} {}", name.green(), "FAILED".red().bold(), message.red(), width = 25);
+      }
+      on_fail
+    }
+  }
+}
+
+/// A request that already got its response, for unit tests of the steps running requests.
+/// Its item has a `name`, the `status` it answered and an optional `check` block.
+#[cfg(test)]
+pub struct HarrawTestResponse {
+  name: String,
+  status: u16,
+  checks: Option<check::HarrawChecks>,
+}
+
+#[cfg(test)]
+impl HarrawTestResponse {
+  pub fn new(item: &Yaml) -> HarrawTestResponse {
+    HarrawTestResponse {
+      name: hrw_extract(item, "name"),
+      status: item["status"].as_i64().expect("`status` needs to be a number") as u16,
+      checks: check::HarrawChecks::new("benchmark.yml", item),
+    }
+  }
+}
+
+#[cfg(test)]
+#[async_trait]
+impl HarrawRunnable for HarrawTestResponse {
+  async fn hrw_execute(&self, _context: &mut Context, reports: &mut Reports, _pool: &Pool, config: &HarrawConfig) -> HarrawFlow {
+    let headers = std::collections::HashMap::new();
+    let response = check::HarrawCheckedResponse {
+      status: self.status,
+      headers: &headers,
+      body: "",
+      duration_ms: 1.0,
+    };
+
+    reports.requests.push(HarrawReport {
+      name: self.name.clone(),
+      duration: 1.0,
+      status: self.status,
+    });
+
+    match self.checks {
+      Some(ref checks) => checks.hrw_evaluate(&self.name, Some(&response), reports, config),
+      None => HarrawFlow::Continue,
+    }
+  }
+}
+
 pub fn hrw_extract_optional<'a>(item: &'a Yaml, attr: &'a str) -> Option<String> {
   if let Some(s) = item[attr].as_str() {
     Some(s.to_string())
@@ -60,4 +181,63 @@ pub fn hrw_extract<'a>(item: &'a Yaml, attr: &'a str) -> String {
   } else {
     panic!("Unknown node `{}` => {:?}", attr, item[attr]);
   }
-}
\ No newline at end of file
+}
+
+pub fn hrw_yaml_to_json(data: Yaml) -> Value {
+  if let Some(b) = data.as_bool() {
+    json!(b)
+  } else if let Some(i) = data.as_i64() {
+    json!(i)
+  } else if let Some(f) = data.as_f64() {
+    json!(f)
+  } else if let Some(s) = data.as_str() {
+    json!(s)
+  } else if let Some(h) = data.as_hash() {
+    let mut map = Map::new();
+
+    for (key, value) in h.iter() {
+      map.entry(key.as_str().unwrap()).or_insert(hrw_yaml_to_json(value.clone()));
+    }
+
+    json!(map)
+  } else if let Some(v) = data.as_vec() {
+    let mut array = Vec::new();
+
+    for value in v.iter() {
+      array.push(hrw_yaml_to_json(value.clone()));
+    }
+
+    json!(array)
+  } else if data.is_null() {
+    Value::Null
+  } else {
+    panic!("Unknown Yaml node")
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn hrw_parse_flows() {
+    assert_eq!(HarrawFlow::new("continue"), HarrawFlow::Continue);
+    assert_eq!(HarrawFlow::new("abort_iteration"), HarrawFlow::AbortIteration);
+    assert_eq!(HarrawFlow::new("skip_rest_of_iteration"), HarrawFlow::AbortIteration);
+    assert_eq!(HarrawFlow::new("abort_run"), HarrawFlow::AbortRun);
+  }
+
+  #[test]
+  #[should_panic(expected = "Unknown failure policy `retry`")]
+  fn hrw_parse_unknown_flow() {
+    HarrawFlow::new("retry");
+  }
+
+  #[test]
+  fn hrw_most_severe_flow_wins() {
+    assert_eq!(HarrawFlow::Continue.max(HarrawFlow::AbortIteration), HarrawFlow::AbortIteration);
+    assert_eq!(HarrawFlow::AbortRun.max(HarrawFlow::AbortIteration), HarrawFlow::AbortRun);
+    assert_eq!(HarrawFlow::Continue.max(HarrawFlow::Continue), HarrawFlow::Continue);
+    assert_eq!(vec![HarrawFlow::AbortIteration, HarrawFlow::AbortRun, HarrawFlow::Continue].into_iter().max(), Some(HarrawFlow::AbortRun));
+  }
+}
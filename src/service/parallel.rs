@@ -0,0 +1,167 @@
+use std::time::Instant;
+
+use async_trait::async_trait;
+use colored::*;
+use futures::stream::{self, StreamExt};
+use futures::FutureExt;
+use yaml_rust::Yaml;
+
+use crate::benchmark::{Benchmark, Context, Pool, Reports};
+use crate::config::HarrawConfig;
+use crate::random;
+use crate::service::{hrw_extract, HarrawFlow, HarrawRunnable, HarrawTransactionReport};
+
+/// Steps run concurrently within the iteration, like a browser fetching the resources of a page.
+pub struct HarrawParallel {
+  name: String,
+  max_parallel: usize,
+  items: Benchmark,
+}
+
+impl HarrawParallel {
+  pub fn hrw_is_that_you(item: &Yaml) -> bool {
+    item["parallel"].as_vec().is_some()
+  }
+
+  /// The nested plan items of the block.
+  pub fn hrw_items(item: &Yaml) -> &Vec<Yaml> {
+    item["parallel"].as_vec().unwrap()
+  }
+
+  pub fn new(item: &Yaml, items: Benchmark) -> HarrawParallel {
+    let name = hrw_extract(item, "name");
+    let max_parallel = match &item["max_parallel"] {
+      Yaml::BadValue => items.len(),
+      value => value.as_i64().filter(|v| *v > 0).unwrap_or_else(|| panic!("`max_parallel` needs to be a positive number, but was {:?}", value)) as usize,
+    };
+
+    HarrawParallel {
+      name,
+      max_parallel,
+      items,
+    }
+  }
+}
+
+/// Copies into `context` every value a step added or changed in its own copy of `original`.
+fn hrw_merge(context: &mut Context, original: &Context, changed: Context) {
+  for (key, value) in changed {
+    if original.get(&key) != Some(&value) {
+      context.insert(key, value);
+    }
+  }
+}
+
+#[async_trait]
+impl HarrawRunnable for HarrawParallel {
+  async fn hrw_execute(&self, context: &mut Context, reports: &mut Reports, pool: &Pool, config: &HarrawConfig) -> HarrawFlow {
+    let begin = Instant::now();
+    let original = context.clone();
+
+    // Every step gets its own copy of the context and its own reports, merged back in plan order
+    let mut children = Vec::new();
+
+    for item in self.items.iter() {
+      let mut child_context = original.clone();
+
+      children.push(random::hrw_scope_child(async move {
+        let mut child_reports = Reports::default();
+        let flow = item.hrw_execute(&mut child_context, &mut child_reports, pool, config).await;
+
+        (child_context, child_reports, flow)
+      }).boxed());
+    }
+    let results: Vec<(Context, Reports, HarrawFlow)> = stream::iter(children).buffered(self.max_parallel.max(1)).collect().await;
+
+    let duration = begin.elapsed().as_secs_f64() * 1000.0;
+    let mut flow = HarrawFlow::Continue;
+    let mut passed = true;
+
+    for (child_context, child_reports, child_flow) in results {
+      hrw_merge(context, &original, child_context);
+
+      passed &= child_flow == HarrawFlow::Continue && child_reports.checks.iter().all(|c| c.passed);
+      flow = flow.max(child_flow);
+
+      reports.requests.extend(child_reports.requests);
+      reports.checks.extend(child_reports.checks);
+      reports.skipped.extend(child_reports.skipped);
+      reports.transactions.extend(child_reports.transactions);
+    }
+
+    reports.transactions.push(HarrawTransactionReport {
+      name: self.name.clone(),
+      duration,
+      passed,
+    });
+
+    if !config.quiet {
+      let status = if passed { "OK".yellow() } else { "FAILED".red().bold() };
+      println!("{:width$} {} {}", self.name.green(), status, format!("{}ms", duration.round()).cyan(), width = 25);
+    }
+    flow
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::backend::include::hrw_expand_items;
+  use crate::tags::HarrawTags;
+  use crate::service::HarrawTestResponse;
+  use crate::benchmark::{hrw_test_block_on, hrw_test_pool};
+  use serde_json::json;
+
+  #[test]
+  fn hrw_merges_the_assignments_back() {
+    let text = "---\nname: Page\nmax_parallel: 2\nparallel:\n  - name: Styles\n    assign:\n      key: styles\n      value: loaded\n  - name: Wait\n    delay:\n      seconds: 0\n  - name: Scripts\n    assign:\n      key: scripts\n      value: loaded";
+    let docs = yaml_rust::YamlLoader::load_from_str(text).unwrap();
+    let mut items = Benchmark::new();
+    hrw_expand_items("benchmark.yml", HarrawParallel::hrw_items(&docs[0]), &mut items, &HarrawTags::new(None, None));
+
+    let parallel = HarrawParallel::new(&docs[0], items);
+    let mut context = Context::new();
+    let mut reports = Reports::default();
+    context.insert("styles".to_string(), json!("pending"));
+
+    let flow = hrw_test_block_on(parallel.hrw_execute(&mut context, &mut reports, &hrw_test_pool(), &HarrawConfig::hrw_test(false)));
+
+    assert_eq!(flow, HarrawFlow::Continue);
+    assert_eq!(parallel.max_parallel, 2);
+    assert_eq!(context.get("styles"), Some(&json!("loaded")));
+    assert_eq!(context.get("scripts"), Some(&json!("loaded")));
+    assert!(reports.transactions[0].passed);
+  }
+
+  #[test]
+  fn hrw_accepted_statuses_pass() {
+    let parallel = |status: &str| {
+      let text = format!("---\nname: Page\nparallel:\n  - name: Favicon\n    status: 200\n  - name: Missing image\n    status: 404\n    check:\n      status: {}", status);
+      let docs = yaml_rust::YamlLoader::load_from_str(&text).unwrap();
+      let items: Benchmark = docs[0]["parallel"].as_vec().unwrap().iter().map(|item| Box::new(HarrawTestResponse::new(item)) as Box<dyn HarrawRunnable + Sync + Send>).collect();
+      HarrawParallel::new(&docs[0], items)
+    };
+    let config = HarrawConfig::hrw_test(false);
+
+    let mut reports = Reports::default();
+    hrw_test_block_on(parallel("404").hrw_execute(&mut Context::new(), &mut reports, &hrw_test_pool(), &config));
+    assert_eq!(reports.requests.len(), 2);
+    assert!(reports.transactions[0].passed);
+
+    let mut reports = Reports::default();
+    hrw_test_block_on(parallel("200").hrw_execute(&mut Context::new(), &mut reports, &hrw_test_pool(), &config));
+    assert!(!reports.transactions[0].passed);
+  }
+
+  #[test]
+  fn hrw_merge_keeps_unchanged_values() {
+    let mut original = Context::new();
+    original.insert("token".to_string(), json!("a"));
+
+    let mut context = original.clone();
+    context.insert("token".to_string(), json!("b"));
+    hrw_merge(&mut context, &original, original.clone());
+
+    assert_eq!(context.get("token"), Some(&json!("b")));
+  }
+}
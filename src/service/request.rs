@@ -14,12 +14,14 @@ use yaml_rust::Yaml;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Map, Value};
 
-use crate::service::{hrw_extract, hrw_extract_optional};
+use crate::service::{hrw_extract, hrw_extract_optional, hrw_read_on_error, hrw_yaml_to_json};
 use crate::benchmark::{Context, Pool, Reports};
 use crate::config::HarrawConfig;
 use crate::interpolator;
 
-use crate::service::{HarrawReport, HarrawRunnable};
+use crate::service::check::{HarrawCheckedResponse, HarrawChecks};
+use crate::service::extract::HarrawExtractions;
+use crate::service::{HarrawFlow, HarrawReport, HarrawRunnable};
 
 static USER_AGENT: &str = "Harraw";
 
@@ -35,6 +37,9 @@ pub struct HarrawRequest {
   pub with_item: Option<Yaml>,
   pub index: Option<u32>,
   pub assign: Option<String>,
+  pub checks: Option<HarrawChecks>,
+  pub extractions: Option<HarrawExtractions>,
+  pub on_error: Option<HarrawFlow>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -49,10 +54,13 @@ impl HarrawRequest {
     item["request"].as_hash().is_some()
   }
 
-  pub fn new(item: &Yaml, with_item: Option<Yaml>, index: Option<u32>) -> HarrawRequest {
+  pub fn new(parent_path: &str, item: &Yaml, with_item: Option<Yaml>, index: Option<u32>) -> HarrawRequest {
     let name = hrw_extract(item, "name");
     let url = hrw_extract(&item["request"], "url");
     let assign = hrw_extract_optional(item, "assign");
+    let checks = HarrawChecks::new(parent_path, item);
+    let extractions = HarrawExtractions::new(item);
+    let on_error = hrw_read_on_error(item);
 
     let method = if let Some(v) = hrw_extract_optional(&item["request"], "method") {
       v.to_uppercase()
@@ -60,7 +68,7 @@ impl HarrawRequest {
       "GET".to_string()
     };
 
-    let body_verbs = vec!["POST", "PATCH", "PUT"];
+    let body_verbs = ["POST", "PATCH", "PUT"];
     let body = if body_verbs.contains(&method.as_str()) {
       Some(hrw_extract(&item["request"], "body"))
     } else {
@@ -78,7 +86,24 @@ impl HarrawRequest {
         }
       }
     }
-    HarrawRequest {name,url,time: 0.0,method, headers,body, with_item,index,assign }
+    HarrawRequest {name,url,time: 0.0,method, headers,body, with_item,index,assign,checks,extractions,on_error }
+  }
+
+  /// Runs the extractions and then the checks of the request against its response.
+  fn hrw_inspect_response(&self, response: Option<&HarrawCheckedResponse>, context: &mut Context, reports: &mut Reports, config: &HarrawConfig) -> HarrawFlow {
+    let mut flow = HarrawFlow::Continue;
+
+    if let Some(ref extractions) = self.extractions {
+      flow = flow.max(extractions.hrw_extract(&self.name, response, context, reports, config));
+    }
+
+    if let Some(ref checks) = self.checks {
+      flow = flow.max(checks.hrw_evaluate(&self.name, response, reports, config));
+    } else if response.is_some_and(|r| r.status / 100 != 2) {
+      // Without checks of its own, an error status fails the step
+      flow = flow.max(self.on_error.unwrap_or(config.on_error));
+    }
+    flow
   }
 
   fn hrw_format_time(tdiff: f64, nanosec: bool) -> String {
@@ -124,7 +149,15 @@ impl HarrawRequest {
       interpolated_url
     };
 
-    let url = Url::parse(&interpolated_base_url).expect("Invalid url!");
+    let url = match Url::parse(&interpolated_base_url) {
+      Ok(url) => url,
+      Err(e) => {
+        if !config.quiet || config.verbose {
+          println!("{:width$} {} {}", interpolated_name.green(), "Invalid url".red().bold(), format!("'{interpolated_base_url}': {e}").red(), width = 25);
+        }
+        return (None, 0.0);
+      }
+    };
     let domain = format!("{}://{}:{}", url.scheme(), url.host_str().unwrap(), url.port().unwrap_or(0)); // Unique domain key for keep-alive
 
     let interpolated_body;
@@ -166,8 +199,8 @@ impl HarrawRequest {
       headers.insert(header::COOKIE, HeaderValue::from_str(&cookie).unwrap());
     }
 
-    // Resolve headers
-    for (key, val) in self.headers.iter() {
+    // Resolve headers, the request ones take precedence over the benchmark ones
+    for (key, val) in config.headers.iter().chain(self.headers.iter()) {
       let interpolated_header = uninterpolator.get_or_insert(interpolator::HarrawInterpolator::new(context)).hrw_resolve(val, !config.relaxed_interpolations);
       headers.insert(HeaderName::from_bytes(key.as_bytes()).unwrap(), HeaderValue::from_str(&interpolated_header).unwrap());
     }
@@ -208,43 +241,15 @@ impl HarrawRequest {
   }
 }
 
-fn hrw_yaml_to_json(data: Yaml) -> Value {
-  if let Some(b) = data.as_bool() {
-    json!(b)
-  } else if let Some(i) = data.as_i64() {
-    json!(i)
-  } else if let Some(s) = data.as_str() {
-    json!(s)
-  } else if let Some(h) = data.as_hash() {
-    let mut map = Map::new();
-
-    for (key, value) in h.iter() {
-      map.entry(key.as_str().unwrap()).or_insert(hrw_yaml_to_json(value.clone()));
-    }
-
-    json!(map)
-  } else if let Some(v) = data.as_vec() {
-    let mut array = Vec::new();
-
-    for value in v.iter() {
-      array.push(hrw_yaml_to_json(value.clone()));
-    }
-
-    json!(array)
-  } else {
-    panic!("Unknown Yaml node")
-  }
-}
-
 #[async_trait]
 impl HarrawRunnable for HarrawRequest {
-  async fn hrw_execute(&self, context: &mut Context, reports: &mut Reports, pool: &Pool, config: &HarrawConfig) {
+  async fn hrw_execute(&self, context: &mut Context, reports: &mut Reports, pool: &Pool, config: &HarrawConfig) -> HarrawFlow {
     if self.with_item.is_some() {
       context.insert("item".to_string(), hrw_yaml_to_json(self.with_item.clone().unwrap()));
     }
 
-    if self.index.is_some() {
-      context.insert("index".to_string(), json!(self.index.unwrap()));
+    if let Some(index) = self.index {
+      context.insert("index".to_string(), json!(index));
     }
 
     let (res, duration_ms) = self.hrw_send_request(context, pool, config).await;
@@ -256,15 +261,19 @@ impl HarrawRunnable for HarrawRequest {
     };
 
     match res {
-      None => reports.push(HarrawReport {
-        name: self.name.to_owned(),
-        duration: duration_ms,
-        status: 520u16,
-      }),
+      None => {
+        reports.requests.push(HarrawReport {
+          name: self.name.to_owned(),
+          duration: duration_ms,
+          status: 520u16,
+        });
+
+        self.hrw_inspect_response(None, context, reports, config).max(self.on_error.unwrap_or(config.on_error))
+      }
       Some(response) => {
         let status = response.status().as_u16();
 
-        reports.push(HarrawReport {
+        reports.requests.push(HarrawReport {
           name: self.name.to_owned(),
           duration: duration_ms,
           status,
@@ -275,16 +284,26 @@ impl HarrawRunnable for HarrawRequest {
           cookies.insert(cookie.name().to_string(), json!(cookie.value().to_string()));
         }
 
-        let data = if let Some(ref key) = self.assign {
-          let mut headers = Map::new();
+        let needs_body = self.assign.is_some() || self.checks.as_ref().is_some_and(|c| c.hrw_needs_body()) || self.extractions.as_ref().is_some_and(|e| e.hrw_needs_body());
+        let response_headers: HashMap<String, String> = response.headers().iter().map(|(header, value)| (header.to_string(), value.to_str().unwrap_or("").to_string())).collect();
+        let data = if needs_body {
+          Some(response.text().await.unwrap_or_default())
+        } else {
+          None
+        };
 
-          response.headers().iter().for_each(|(header, value)| {
-            headers.insert(header.to_string(), json!(value.to_str().unwrap()));
-          });
+        let checked = HarrawCheckedResponse {
+          status,
+          headers: &response_headers,
+          body: data.as_deref().unwrap_or(""),
+          duration_ms,
+        };
+        let flow = self.hrw_inspect_response(Some(&checked), context, reports, config);
 
-          let data = response.text().await.unwrap();
+        if let (Some(ref key), Some(data)) = (&self.assign, &data) {
+          let headers: Map<String, Value> = response_headers.iter().map(|(header, value)| (header.to_owned(), json!(value))).collect();
 
-          let body: Value = serde_json::from_str(&data).unwrap_or(serde_json::Value::Null);
+          let body: Value = serde_json::from_str(data).unwrap_or(serde_json::Value::Null);
 
           let assigned = HarrawAssignedRequest {
             status,
@@ -295,15 +314,12 @@ impl HarrawRunnable for HarrawRequest {
           let value = serde_json::to_value(assigned).unwrap();
 
           context.insert(key.to_owned(), value);
-
-          Some(data)
-        } else {
-          None
-        };
+        }
 
         if let Some(msg) = log_message_response {
             hrw_log_response(msg, &data)
         }
+        flow
       }
     }
   }
@@ -341,4 +357,47 @@ fn hrw_log_response(log_message_response: String, body: &Option<String>) {
     write!(message, " {} {:?}", "BODY:".bold(), body).unwrap()
   }
   println!("{message}");
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn hrw_inspect(text: &str, status: u16) -> (HarrawFlow, Reports) {
+    let docs = yaml_rust::YamlLoader::load_from_str(text).unwrap();
+    let request = HarrawRequest::new("benchmark.yml", &docs[0], None, None);
+    let config = HarrawConfig::hrw_test(false);
+    let headers = HashMap::new();
+    let response = HarrawCheckedResponse {
+      status,
+      headers: &headers,
+      body: "",
+      duration_ms: 1.0,
+    };
+    let mut reports = Reports::default();
+
+    let flow = request.hrw_inspect_response(Some(&response), &mut Context::new(), &mut reports, &config);
+    (flow, reports)
+  }
+
+  #[test]
+  fn hrw_error_status_fails_the_step() {
+    let text = "---\nname: Login\nrequest:\n  url: /api/login\non_error: skip_rest_of_iteration";
+
+    assert_eq!(hrw_inspect(text, 401).0, HarrawFlow::AbortIteration);
+    assert_eq!(hrw_inspect(text, 200).0, HarrawFlow::Continue);
+  }
+
+  #[test]
+  fn hrw_checks_decide_the_expected_status() {
+    let text = "---\nname: Missing user\nrequest:\n  url: /api/users/0\ncheck:\n  status: 404\non_error: skip_rest_of_iteration";
+
+    let (flow, reports) = hrw_inspect(text, 404);
+    assert_eq!(flow, HarrawFlow::Continue);
+    assert!(reports.checks[0].passed);
+
+    let (flow, reports) = hrw_inspect(text, 500);
+    assert_eq!(flow, HarrawFlow::AbortIteration);
+    assert!(!reports.checks[0].passed);
+  }
+}
@@ -0,0 +1,83 @@
+use async_trait::async_trait;
+use yaml_rust::Yaml;
+
+use crate::benchmark::{Benchmark, Context, Pool, Reports};
+use crate::config::HarrawConfig;
+use crate::interpolator::HarrawInterpolator;
+use crate::service::{hrw_yaml_to_json, HarrawFlow, HarrawRunnable};
+
+/// Runs the steps of a parameterised include with its `vars`, which are only
+/// visible to those steps. Anything else they assign stays in the context.
+pub struct HarrawScope {
+  vars: Vec<(String, Yaml)>,
+  items: Benchmark,
+}
+
+impl HarrawScope {
+  pub fn new(vars: &Yaml, items: Benchmark) -> HarrawScope {
+    let vars = vars.as_hash().expect("`vars` of an include needs to be a hash").iter().map(|(key, value)| (key.as_str().expect("`vars` keys must be strings").to_string(), value.clone())).collect();
+
+    HarrawScope {
+      vars,
+      items,
+    }
+  }
+}
+
+#[async_trait]
+impl HarrawRunnable for HarrawScope {
+  async fn hrw_execute(&self, context: &mut Context, reports: &mut Reports, pool: &Pool, config: &HarrawConfig) -> HarrawFlow {
+    let values: Vec<serde_json::Value> = self.vars.iter().map(|(_, value)| match value.as_str() {
+      Some(text) => HarrawInterpolator::new(context).hrw_resolve_value(text, !config.relaxed_interpolations),
+      None => hrw_yaml_to_json(value.clone()),
+    }).collect();
+
+    let mut shadowed = Vec::new();
+
+    for ((key, _), value) in self.vars.iter().zip(values) {
+      shadowed.push((key, context.insert(key.to_owned(), value)));
+    }
+
+    let mut flow = HarrawFlow::Continue;
+
+    for item in self.items.iter() {
+      flow = item.hrw_execute(context, reports, pool, config).await;
+
+      if flow != HarrawFlow::Continue {
+        break;
+      }
+    }
+
+    for (key, previous) in shadowed.into_iter().rev() {
+      match previous {
+        Some(previous) => context.insert(key.to_owned(), previous),
+        None => context.remove(key),
+      };
+    }
+    flow
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::service::assign::HarrawAssign;
+  use crate::benchmark::{hrw_test_block_on, hrw_test_pool};
+  use serde_json::json;
+
+  #[test]
+  fn hrw_vars_are_scoped() {
+    let docs = yaml_rust::YamlLoader::load_from_str("---\nvars:\n  user: \"{{ admin }}\"\n  page: 2\nassign:\n  key: token\n  value: secret\nname: Token").unwrap();
+    let items: Benchmark = vec![Box::new(HarrawAssign::new(&docs[0], None))];
+    let scope = HarrawScope::new(&docs[0]["vars"], items);
+    let mut context = Context::new();
+    context.insert("admin".to_string(), json!("mary"));
+    context.insert("user".to_string(), json!("john"));
+
+    hrw_test_block_on(scope.hrw_execute(&mut context, &mut Reports::default(), &hrw_test_pool(), &HarrawConfig::hrw_test(false)));
+
+    assert_eq!(context.get("user"), Some(&json!("john")));
+    assert_eq!(context.get("page"), None);
+    assert_eq!(context.get("token"), Some(&json!("secret")));
+  }
+}
@@ -0,0 +1,125 @@
+use async_trait::async_trait;
+use colored::*;
+use serde_json::Value;
+use yaml_rust::Yaml;
+
+use crate::benchmark::{Context, Pool, Reports};
+use crate::config::HarrawConfig;
+use crate::interpolator::{hrw_value_to_string, HarrawInterpolator};
+use crate::service::{hrw_extract, hrw_yaml_to_json, HarrawFlow, HarrawRunnable};
+use crate::shared;
+
+/// Writes to and reads from the state shared by every iteration:
+/// `set` stores values, `push` appends them to queues and `pop` takes them into the context.
+#[derive(Clone)]
+pub struct HarrawShare {
+  name: String,
+  set: Vec<(String, Yaml)>,
+  push: Vec<(String, Yaml)>,
+  pop: Vec<(String, String)>,
+}
+
+impl HarrawShare {
+  pub fn hrw_is_that_you(item: &Yaml) -> bool {
+    item["shared"].as_hash().is_some()
+  }
+
+  pub fn new(item: &Yaml) -> HarrawShare {
+    let name = hrw_extract(item, "name");
+    let mut share = HarrawShare {
+      name,
+      set: Vec::new(),
+      push: Vec::new(),
+      pop: Vec::new(),
+    };
+
+    for (operation, entries) in item["shared"].as_hash().unwrap().iter() {
+      let operation = operation.as_str().expect("`shared` keys must be strings");
+      let entries = entries.as_hash().unwrap_or_else(|| panic!("`shared.{}` needs to be a hash", operation));
+
+      for (key, value) in entries.iter() {
+        let key = key.as_str().unwrap_or_else(|| panic!("`shared.{}` keys must be strings", operation)).to_string();
+
+        match operation {
+          "set" => share.set.push((key, value.clone())),
+          "push" => share.push.push((key, value.clone())),
+          "pop" => {
+            let queue = value.as_str().unwrap_or_else(|| panic!("`shared.pop.{}` needs a queue name", key)).to_string();
+            share.pop.push((key, queue));
+          }
+          _ => panic!("Unknown shared operation `{}`, expected set, push or pop", operation),
+        }
+      }
+    }
+    share
+  }
+
+  fn hrw_value(value: &Yaml, context: &Context, config: &HarrawConfig) -> Value {
+    match value.as_str() {
+      Some(text) => HarrawInterpolator::new(context).hrw_resolve_value(text, !config.relaxed_interpolations),
+      None => hrw_yaml_to_json(value.clone()),
+    }
+  }
+}
+
+#[async_trait]
+impl HarrawRunnable for HarrawShare {
+  async fn hrw_execute(&self, context: &mut Context, _reports: &mut Reports, _pool: &Pool, config: &HarrawConfig) -> HarrawFlow {
+    for (key, value) in self.set.iter() {
+      let value = HarrawShare::hrw_value(value, context, config);
+
+      if !config.quiet {
+        println!("{:width$} {}={}", self.name.green(), key.cyan().bold(), hrw_value_to_string(&value).magenta(), width = 25);
+      }
+      shared::hrw_set(key, value);
+    }
+
+    for (queue, value) in self.push.iter() {
+      let value = HarrawShare::hrw_value(value, context, config);
+
+      if !config.quiet {
+        println!("{:width$} {} << {}", self.name.green(), queue.cyan().bold(), hrw_value_to_string(&value).magenta(), width = 25);
+      }
+      shared::hrw_push(queue, value);
+    }
+
+    for (key, queue) in self.pop.iter() {
+      let value = shared::hrw_pop(queue).unwrap_or(Value::Null);
+
+      if !config.quiet {
+        println!("{:width$} {}={}", self.name.green(), key.cyan().bold(), hrw_value_to_string(&value).magenta(), width = 25);
+      }
+      context.insert(key.to_owned(), value);
+    }
+    HarrawFlow::Continue
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::benchmark::{hrw_test_block_on, hrw_test_pool};
+  use serde_json::json;
+
+  #[test]
+  fn hrw_hands_values_between_iterations() {
+    let producer = "---\nname: Created\nshared:\n  set:\n    share_last: \"{{ order.id }}\"\n  push:\n    share_orders: \"{{ order }}\"";
+    let consumer = "---\nname: Cancel\nshared:\n  pop:\n    cancel: share_orders";
+    let producer = HarrawShare::new(&yaml_rust::YamlLoader::load_from_str(producer).unwrap()[0]);
+    let consumer = HarrawShare::new(&yaml_rust::YamlLoader::load_from_str(consumer).unwrap()[0]);
+    let config = HarrawConfig::hrw_test(false);
+    let pool = hrw_test_pool();
+
+    let mut first = Context::new();
+    first.insert("order".to_string(), json!({"id": 42}));
+    hrw_test_block_on(producer.hrw_execute(&mut first, &mut Reports::default(), &pool, &config));
+
+    let mut second = Context::new();
+    hrw_test_block_on(consumer.hrw_execute(&mut second, &mut Reports::default(), &pool, &config));
+    assert_eq!(second.get("cancel"), Some(&json!({"id": 42})));
+    assert_eq!(shared::hrw_get("share_last"), Some(json!(42)));
+
+    hrw_test_block_on(consumer.hrw_execute(&mut second, &mut Reports::default(), &pool, &config));
+    assert_eq!(second.get("cancel"), Some(&Value::Null));
+  }
+}
@@ -0,0 +1,197 @@
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use tokio::time::sleep;
+use yaml_rust::Yaml;
+
+use crate::benchmark::{Benchmark, Context, Pool, Reports};
+use crate::config::HarrawConfig;
+use crate::interpolator::HarrawInterpolator;
+use crate::service::{hrw_read_on_error, hrw_record_check, HarrawFlow, HarrawRunnable, HarrawTransactionReport};
+
+static DEFAULT_RETRIES: usize = 3;
+static DEFAULT_DELAY: Duration = Duration::from_secs(1);
+
+/// Polls the items expanded from one plan entry until their `until:` condition holds,
+/// like a job endpoint answering `202` until the work is done.
+pub struct HarrawUntil {
+  name: String,
+  condition: String,
+  retries: usize,
+  delay: Duration,
+  on_error: Option<HarrawFlow>,
+  items: Benchmark,
+}
+
+impl HarrawUntil {
+  pub fn hrw_is_that_you(item: &Yaml) -> bool {
+    match &item["until"] {
+      Yaml::BadValue => false,
+      Yaml::String(_) => true,
+      _ => panic!("`until` needs to be a string like \"{{{{ job.body.state }}}} == 'done'\""),
+    }
+  }
+
+  pub fn new(item: &Yaml, items: Benchmark) -> HarrawUntil {
+    let condition = item["until"].as_str().unwrap().to_string();
+    let name = item["name"].as_str().unwrap_or(&condition).to_string();
+    let retries = match &item["retries"] {
+      Yaml::BadValue => DEFAULT_RETRIES,
+      value => value.as_i64().and_then(|v| usize::try_from(v).ok()).unwrap_or_else(|| panic!("`retries` needs to be a positive number, but was {:?}", value)),
+    };
+    let delay = match &item["delay"] {
+      Yaml::BadValue => DEFAULT_DELAY,
+      value => hrw_parse_duration(value),
+    };
+
+    HarrawUntil {
+      name,
+      condition,
+      retries,
+      delay,
+      on_error: hrw_read_on_error(item),
+      items,
+    }
+  }
+
+  fn hrw_is_met(&self, context: &Context) -> Result<bool, String> {
+    HarrawInterpolator::new(context).hrw_condition(&self.condition).map_err(|error| error.to_string())
+  }
+}
+
+/// Reads a duration like `500ms`, `2s` or `1m`, plain numbers are seconds.
+pub fn hrw_parse_duration(value: &Yaml) -> Duration {
+  if let Some(seconds) = value.as_i64().and_then(|v| u64::try_from(v).ok()) {
+    return Duration::from_secs(seconds);
+  }
+  if let Some(seconds) = value.as_f64().filter(|v| *v >= 0.0) {
+    return Duration::from_secs_f64(seconds);
+  }
+
+  let text = value.as_str().map(str::trim).unwrap_or_default();
+  let (number, unit) = text.split_at(text.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(text.len()));
+  let number: f64 = number.parse().unwrap_or_else(|_| panic!("Invalid duration {:?}, expected something like 500ms, 2s or 1m", value));
+
+  match unit.trim() {
+    "ms" => Duration::from_secs_f64(number / 1000.0),
+    "" | "s" => Duration::from_secs_f64(number),
+    "m" => Duration::from_secs_f64(number * 60.0),
+    _ => panic!("Invalid duration {:?}, expected something like 500ms, 2s or 1m", value),
+  }
+}
+
+#[async_trait]
+impl HarrawRunnable for HarrawUntil {
+  async fn hrw_execute(&self, context: &mut Context, reports: &mut Reports, pool: &Pool, config: &HarrawConfig) -> HarrawFlow {
+    let begin = Instant::now();
+    let mut result = Ok(false);
+
+    for attempt in 0..=self.retries {
+      if attempt > 0 {
+        sleep(self.delay).await;
+      }
+
+      for item in self.items.iter() {
+        let flow = item.hrw_execute(context, reports, pool, config).await;
+
+        if flow != HarrawFlow::Continue {
+          reports.transactions.push(HarrawTransactionReport {
+            name: self.name.clone(),
+            duration: begin.elapsed().as_secs_f64() * 1000.0,
+            passed: false,
+          });
+          return flow;
+        }
+      }
+
+      result = self.hrw_is_met(context);
+
+      if result == Ok(true) {
+        break;
+      }
+    }
+
+    let passed = result == Ok(true);
+
+    reports.transactions.push(HarrawTransactionReport {
+      name: self.name.clone(),
+      duration: begin.elapsed().as_secs_f64() * 1000.0,
+      passed,
+    });
+
+    if passed {
+      return HarrawFlow::Continue;
+    }
+
+    let reason = match result {
+      Err(error) => format!(", last error: {error}"),
+      _ => String::new(),
+    };
+    let message = format!("`{}` not met after {} attempts{}", self.condition, self.retries + 1, reason);
+
+    hrw_record_check(reports, config, &self.name, Err(message), self.on_error.unwrap_or(config.on_error))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::service::assert::HarrawAssert;
+  use crate::benchmark::{hrw_test_block_on, hrw_test_pool};
+  use serde_json::json;
+
+  fn hrw_until(text: &str) -> HarrawUntil {
+    let docs = yaml_rust::YamlLoader::load_from_str(text).unwrap();
+    HarrawUntil::new(&docs[0], Benchmark::new())
+  }
+
+  fn hrw_run(until: &HarrawUntil, context: &mut Context) -> Reports {
+    let mut reports = Reports::default();
+
+    hrw_test_block_on(until.hrw_execute(context, &mut reports, &hrw_test_pool(), &HarrawConfig::hrw_test(false)));
+    reports
+  }
+
+  #[test]
+  fn hrw_parses_durations() {
+    let docs = yaml_rust::YamlLoader::load_from_str("[500ms, 2s, 1m, 3, 0.5]").unwrap();
+    let durations: Vec<Duration> = docs[0].as_vec().unwrap().iter().map(hrw_parse_duration).collect();
+
+    assert_eq!(durations, vec![Duration::from_millis(500), Duration::from_secs(2), Duration::from_secs(60), Duration::from_secs(3), Duration::from_millis(500)]);
+  }
+
+  #[test]
+  fn hrw_stops_once_met() {
+    let until = hrw_until("---\nname: Poll job\nuntil: \"{{ job.state }} == 'done'\"\nretries: 5\ndelay: 10s");
+    let mut context = Context::new();
+    context.insert("job".to_string(), json!({"state": "done"}));
+
+    let reports = hrw_run(&until, &mut context);
+
+    assert!(reports.checks.is_empty());
+    assert!(reports.transactions[0].passed);
+    assert!(reports.transactions[0].duration < 1000.0);
+  }
+
+  #[test]
+  fn hrw_fails_when_never_met() {
+    let until = hrw_until("---\nname: Poll job\nuntil: \"{{ job.state }} == 'done'\"\nretries: 2\ndelay: 1ms");
+    let reports = hrw_run(&until, &mut Context::new());
+
+    assert!(!reports.transactions[0].passed);
+    assert_eq!(reports.checks.len(), 1);
+    assert!(!reports.checks[0].passed);
+  }
+
+  #[test]
+  fn hrw_records_aborted_polls() {
+    let docs = yaml_rust::YamlLoader::load_from_str("---\nname: Poll job\nuntil: \"{{ job.state }} == 'done'\"\nretries: 5\ndelay: 1ms\nassert:\n  key: job.state\n  exists: true\n  on_fail: abort_iteration").unwrap();
+    let until = HarrawUntil::new(&docs[0], vec![Box::new(HarrawAssert::new("benchmark.yml", &docs[0], None))]);
+    let reports = hrw_run(&until, &mut Context::new());
+
+    assert_eq!(reports.transactions.len(), 1);
+    assert_eq!(reports.transactions[0].name, "Poll job");
+    assert!(!reports.transactions[0].passed);
+    assert_eq!(reports.checks.len(), 1);
+  }
+}
@@ -0,0 +1,113 @@
+use async_trait::async_trait;
+use colored::*;
+use yaml_rust::Yaml;
+
+use crate::benchmark::{Benchmark, Context, Pool, Reports};
+use crate::config::HarrawConfig;
+use crate::interpolator::HarrawInterpolator;
+use crate::service::{HarrawFlow, HarrawRunnable};
+
+/// Guards the items expanded from one plan entry with its `when:` condition,
+/// evaluated once per iteration before any of them runs.
+pub struct HarrawWhen {
+  name: String,
+  condition: String,
+  items: Benchmark,
+}
+
+impl HarrawWhen {
+  pub fn hrw_is_that_you(item: &Yaml) -> bool {
+    match &item["when"] {
+      Yaml::BadValue => false,
+      Yaml::String(_) => true,
+      _ => panic!("`when` needs to be a string like \"{{{{ login.status }}}} == 200\""),
+    }
+  }
+
+  pub fn new(item: &Yaml, items: Benchmark) -> HarrawWhen {
+    let condition = item["when"].as_str().unwrap().to_string();
+    let name = item["name"].as_str().unwrap_or(&condition).to_string();
+
+    HarrawWhen {
+      name,
+      condition,
+      items,
+    }
+  }
+
+  fn hrw_should_run(&self, context: &Context, config: &HarrawConfig) -> bool {
+    let interpolator = HarrawInterpolator::new(context);
+
+    match interpolator.hrw_condition(&self.condition) {
+      Ok(result) => result,
+      Err(error) => {
+        if !config.relaxed_interpolations {
+          panic!("Invalid `when` condition `{}`: {}", self.condition, error);
+        }
+        eprintln!("{} Invalid `when` condition `{}`: {}", "WARNING!".yellow().bold(), self.condition, error);
+        false
+      }
+    }
+  }
+}
+
+#[async_trait]
+impl HarrawRunnable for HarrawWhen {
+  async fn hrw_execute(&self, context: &mut Context, reports: &mut Reports, pool: &Pool, config: &HarrawConfig) -> HarrawFlow {
+    if !self.hrw_should_run(context, config) {
+      reports.skipped.push(self.name.clone());
+
+      if !config.quiet {
+        println!("{:width$} {}", self.name.green(), "SKIPPED".yellow(), width = 25);
+      }
+      return HarrawFlow::Continue;
+    }
+
+    for item in self.items.iter() {
+      let flow = item.hrw_execute(context, reports, pool, config).await;
+
+      if flow != HarrawFlow::Continue {
+        return flow;
+      }
+    }
+    HarrawFlow::Continue
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serde_json::json;
+
+  fn hrw_when(text: &str) -> HarrawWhen {
+    let docs = yaml_rust::YamlLoader::load_from_str(text).unwrap();
+    HarrawWhen::new(&docs[0], Benchmark::new())
+  }
+
+  #[test]
+  fn hrw_evaluates_the_condition() {
+    let when = hrw_when("---\nname: Profile\nwhen: \"{{ login.status }} == 200\"");
+    let mut context = Context::new();
+
+    context.insert("login".to_string(), json!({"status": 200}));
+    assert!(when.hrw_should_run(&context, &HarrawConfig::hrw_test(false)));
+
+    context.insert("login".to_string(), json!({"status": 401}));
+    assert!(!when.hrw_should_run(&context, &HarrawConfig::hrw_test(false)));
+  }
+
+  #[test]
+  fn hrw_relaxed_invalid_condition_skips() {
+    let when = hrw_when("---\nname: Profile\nwhen: \"{{ login.status }} == 200\"");
+
+    assert!(!when.hrw_should_run(&Context::new(), &HarrawConfig::hrw_test(true)));
+  }
+
+  #[test]
+  #[should_panic(expected = "Invalid `when` condition `{{ login.status }} == 200`: Unknown 'login.status' variable!")]
+  fn hrw_strict_invalid_condition_panics() {
+    let when = hrw_when("---\nname: Profile\nwhen: \"{{ login.status }} == 200\"");
+
+    when.hrw_should_run(&Context::new(), &HarrawConfig::hrw_test(false));
+  }
+}
@@ -0,0 +1,72 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+
+use lazy_static::lazy_static;
+use serde_json::Value;
+
+use crate::benchmark::Context;
+
+lazy_static! {
+  static ref SHARED: Mutex<HarrawShared> = Mutex::new(HarrawShared::default());
+}
+
+/// State shared by every iteration of the run, so one flow can hand values to another.
+#[derive(Default)]
+struct HarrawShared {
+  counters: HashMap<String, i64>,
+  store: Context,
+  queues: HashMap<String, VecDeque<Value>>,
+}
+
+/// Increments a global counter and returns its new value, starting at 1.
+pub fn hrw_counter(name: &str) -> i64 {
+  let mut shared = SHARED.lock().unwrap();
+  let counter = shared.counters.entry(name.to_string()).or_insert(0);
+
+  *counter += 1;
+  *counter
+}
+
+pub fn hrw_get(key: &str) -> Option<Value> {
+  SHARED.lock().unwrap().store.get(key).cloned()
+}
+
+pub fn hrw_set(key: &str, value: Value) {
+  SHARED.lock().unwrap().store.insert(key.to_string(), value);
+}
+
+/// Appends a value to the back of a queue.
+pub fn hrw_push(queue: &str, value: Value) {
+  SHARED.lock().unwrap().queues.entry(queue.to_string()).or_default().push_back(value);
+}
+
+/// Takes the oldest value of a queue, so every value is consumed once.
+pub fn hrw_pop(queue: &str) -> Option<Value> {
+  SHARED.lock().unwrap().queues.get_mut(queue).and_then(VecDeque::pop_front)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serde_json::json;
+
+  #[test]
+  fn hrw_counts_across_threads() {
+    let threads: Vec<_> = (0..4).map(|_| std::thread::spawn(|| (0..25).map(|_| hrw_counter("threads")).collect::<Vec<i64>>())).collect();
+    let mut values: Vec<i64> = threads.into_iter().flat_map(|thread| thread.join().unwrap()).collect();
+    values.sort();
+
+    assert_eq!(values, (1..=100).collect::<Vec<i64>>());
+  }
+
+  #[test]
+  fn hrw_queues_are_fifo() {
+    hrw_push("orders", json!(1));
+    hrw_push("orders", json!({"id": 2}));
+
+    assert_eq!(hrw_pop("orders"), Some(json!(1)));
+    assert_eq!(hrw_pop("orders"), Some(json!({"id": 2})));
+    assert_eq!(hrw_pop("orders"), None);
+    assert_eq!(hrw_pop("unknown"), None);
+  }
+}
@@ -1,23 +1,164 @@
+use crate::backend::include;
 use crate::reader;
 use colored::*;
 use std::collections::HashSet;
+use std::fmt;
 use yaml_rust::{Yaml, YamlEmitter};
 
 
+/// A `--tags` or `--skip-tags` selection like `smoke and not slow` or `(api or web) and !flaky`.
+/// Commas are read as `or`, so plain lists like `api,web` keep working.
+#[derive(Clone, Debug, PartialEq)]
+pub enum HarrawTagExpression<'a> {
+    Tag(&'a str),
+    Not(Box<HarrawTagExpression<'a>>),
+    And(Box<HarrawTagExpression<'a>>, Box<HarrawTagExpression<'a>>),
+    Or(Box<HarrawTagExpression<'a>>, Box<HarrawTagExpression<'a>>),
+}
+
+impl <'a> HarrawTagExpression<'a> {
+    pub fn hrw_parse(source: &'a str) -> Result<HarrawTagExpression<'a>, String> {
+        let tokens = hrw_tokenize(source);
+        let mut position = 0;
+        let expression = hrw_parse_or(&tokens, &mut position)?;
+
+        match tokens.get(position) {
+            None => Ok(expression),
+            Some(token) => Err(format!("unexpected `{token}`")),
+        }
+    }
+
+    pub fn hrw_matches(&self, item_tags: &HashSet<&str>) -> bool {
+        match self {
+            HarrawTagExpression::Tag(tag) => item_tags.contains(tag),
+            HarrawTagExpression::Not(operand) => !operand.hrw_matches(item_tags),
+            HarrawTagExpression::And(left, right) => left.hrw_matches(item_tags) && right.hrw_matches(item_tags),
+            HarrawTagExpression::Or(left, right) => left.hrw_matches(item_tags) || right.hrw_matches(item_tags),
+        }
+    }
+
+    /// Whether the expression is a plain list of tags like `api,web` or `api or web`.
+    pub fn hrw_is_list(&self) -> bool {
+        match self {
+            HarrawTagExpression::Tag(_) => true,
+            HarrawTagExpression::Or(left, right) => left.hrw_is_list() && right.hrw_is_list(),
+            HarrawTagExpression::Not(_) | HarrawTagExpression::And(_, _) => false,
+        }
+    }
+
+    /// Every tag named in the expression, whatever its polarity.
+    pub fn hrw_tags(&self) -> HashSet<&'a str> {
+        match self {
+            HarrawTagExpression::Tag(tag) => HashSet::from([*tag]),
+            HarrawTagExpression::Not(operand) => operand.hrw_tags(),
+            HarrawTagExpression::And(left, right) | HarrawTagExpression::Or(left, right) => &left.hrw_tags() | &right.hrw_tags(),
+        }
+    }
+}
+
+impl fmt::Display for HarrawTagExpression<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HarrawTagExpression::Tag(tag) => write!(f, "{tag}"),
+            HarrawTagExpression::Not(operand) => match **operand {
+                HarrawTagExpression::Tag(_) | HarrawTagExpression::Not(_) => write!(f, "not {operand}"),
+                _ => write!(f, "not ({operand})"),
+            },
+            HarrawTagExpression::And(left, right) => {
+                for (index, operand) in [left, right].iter().enumerate() {
+                    if index > 0 {
+                        write!(f, " and ")?;
+                    }
+                    match ***operand {
+                        HarrawTagExpression::Or(_, _) => write!(f, "({operand})")?,
+                        _ => write!(f, "{operand}")?,
+                    }
+                }
+                Ok(())
+            }
+            HarrawTagExpression::Or(left, right) => write!(f, "{left} or {right}"),
+        }
+    }
+}
+
+fn hrw_tokenize(source: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut rest = source.trim_start();
+
+    while let Some(first) = rest.chars().next() {
+        let length = if "(),!".contains(first) {
+            1
+        } else {
+            rest.find(|c: char| c.is_whitespace() || "(),!".contains(c)).unwrap_or(rest.len())
+        };
+
+        tokens.push(&rest[..length]);
+        rest = rest[length..].trim_start();
+    }
+    tokens
+}
+
+fn hrw_parse_or<'a>(tokens: &[&'a str], position: &mut usize) -> Result<HarrawTagExpression<'a>, String> {
+    let mut left = hrw_parse_and(tokens, position)?;
+
+    while matches!(tokens.get(*position), Some(&"or") | Some(&",")) {
+        *position += 1;
+        left = HarrawTagExpression::Or(Box::new(left), Box::new(hrw_parse_and(tokens, position)?));
+    }
+    Ok(left)
+}
+
+fn hrw_parse_and<'a>(tokens: &[&'a str], position: &mut usize) -> Result<HarrawTagExpression<'a>, String> {
+    let mut left = hrw_parse_not(tokens, position)?;
+
+    while tokens.get(*position) == Some(&"and") {
+        *position += 1;
+        left = HarrawTagExpression::And(Box::new(left), Box::new(hrw_parse_not(tokens, position)?));
+    }
+    Ok(left)
+}
+
+fn hrw_parse_not<'a>(tokens: &[&'a str], position: &mut usize) -> Result<HarrawTagExpression<'a>, String> {
+    let token = tokens.get(*position).copied();
+    *position += 1;
+
+    match token {
+        Some("not") | Some("!") => Ok(HarrawTagExpression::Not(Box::new(hrw_parse_not(tokens, position)?))),
+        Some("(") => {
+            let expression = hrw_parse_or(tokens, position)?;
+
+            if tokens.get(*position) != Some(&")") {
+                return Err("missing `)`".to_string());
+            }
+            *position += 1;
+            Ok(expression)
+        }
+        Some(")") | Some(",") | Some("and") | Some("or") => Err(format!("unexpected `{}`", token.unwrap())),
+        Some(tag) => Ok(HarrawTagExpression::Tag(tag)),
+        None => Err("unexpected end".to_string()),
+    }
+}
+
+fn hrw_parse_option<'a>(option: Option<&'a str>, flag: &str) -> Option<HarrawTagExpression<'a>> {
+    option.map(|source| HarrawTagExpression::hrw_parse(source).unwrap_or_else(|error| panic!("Invalid `{}` expression `{}`: {}", flag, source, error)))
+}
+
+
 #[derive(Debug)]
 pub struct HarrawTags<'a> {
-    pub tags: Option<HashSet<&'a str>>,
-    pub skip_tags: Option<HashSet<&'a str>>,
+    pub tags: Option<HarrawTagExpression<'a>>,
+    pub skip_tags: Option<HarrawTagExpression<'a>>,
 }
 
 
 impl <'a> HarrawTags<'a> {
     pub fn new(tags_option: Option<&'a str>, skip_tags_option: Option<&'a str>) -> Self {
-        let tags: Option<HashSet<&str>> = tags_option.map(|m| m.split(',').map(|s|s.trim()).collect());
-        let skip_tags: Option<HashSet<&str>> = skip_tags_option.map(|m| m.split(',').map(|s| s.trim()).collect());
+        let tags = hrw_parse_option(tags_option, "tags");
+        let skip_tags = hrw_parse_option(skip_tags_option, "skip-tags");
 
+        // Only plain lists can contradict each other, expressions like `not slow` may name a skipped tag
         if let (Some(t), Some(s)) = (&tags, &skip_tags) {
-            if !t.is_disjoint(s) {
+            if t.hrw_is_list() && s.hrw_is_list() && !t.hrw_tags().is_disjoint(&s.hrw_tags()) {
                 panic!("`tags` and `skip-tags` must not contain the same values!");
             }
         }
@@ -27,73 +168,91 @@ impl <'a> HarrawTags<'a> {
         }
     }
 
+    /// The selection inside an item that was already picked, like the setup steps:
+    /// they all run unless one of them is skipped explicitly.
+    pub fn hrw_within_selected(&self) -> HarrawTags<'a> {
+        HarrawTags {
+            tags: None,
+            skip_tags: self.skip_tags.clone(),
+        }
+    }
+
+    /// `--skip-tags` wins over everything. Then `always` items run, `never` items only
+    /// run when `--tags` names `never`, and the rest run when they match `--tags`.
     pub fn hrw_should_skip_item(&self, item: &Yaml) -> bool {
-        match item["tags"].as_vec() {
-            Some(item_tags_raw) => {
-                let item_tags: HashSet<&str> = item_tags_raw.iter().map(|t|t.as_str().unwrap()).collect();
-                if let Some(s) = &self.skip_tags {
-                    if !s.is_disjoint(&item_tags) {
-                        return true;
-                    }
-                }
+        let item_tags: HashSet<&str> = item["tags"].as_vec().map(|tags| tags.iter().map(|t| t.as_str().unwrap()).collect()).unwrap_or_default();
 
-                if let Some(t) = &self.tags {
-                    if item_tags.contains("never") && !t.contains("never") {
-                        return true;
-                    }
+        if self.skip_tags.as_ref().is_some_and(|s| s.hrw_matches(&item_tags)) {
+            return true;
+        }
 
-                    if !t.is_disjoint(&item_tags) {
-                        return true;
-                    }
-                }
+        if item_tags.contains("always") {
+            return false;
+        }
 
-                if item_tags.contains("always") {
-                    return false;
-                }
-                if item_tags.contains("always") {
-                    return true;
+        match &self.tags {
+            Some(t) if item_tags.contains("never") => !(t.hrw_tags().contains("never") && t.hrw_matches(&item_tags)),
+            Some(t) => !t.hrw_matches(&item_tags),
+            None => item_tags.contains("never"),
+        }
+    }
+}
+
+/// Adds the tags of an include or a block to its nested items.
+pub fn hrw_inherit_tags(items: &[Yaml], parent: &Yaml) -> Vec<Yaml> {
+    let mut items = items.to_vec();
+
+    let parent_tags = match parent["tags"].as_vec() {
+        Some(parent_tags) => parent_tags,
+        None => return items,
+    };
+
+    for item in items.iter_mut() {
+        if let Yaml::Hash(hash) = item {
+            let key = Yaml::String("tags".to_string());
+            let mut item_tags = hash.get(&key).and_then(Yaml::as_vec).cloned().unwrap_or_default();
+
+            for tag in parent_tags {
+                if !item_tags.contains(tag) {
+                    item_tags.push(tag.clone());
                 }
-                self.tags.is_some()
             }
-            None => self.tags.is_some(),
+            hash.insert(key, Yaml::Array(item_tags));
         }
     }
+    items
 }
 
 pub fn hrw_list_benchmark_file_tasks(benchmark_file: &str, tags: &HarrawTags) {
     let docs = reader::hrw_read_file_as_yml(benchmark_file);
-  let items = reader::hrw_read_yaml_doc_accessor(&docs[0], Some("plan"));
+    let items = include::hrw_walk_items(benchmark_file, reader::hrw_read_yaml_doc_accessor(&docs[0], Some("plan")));
 
-  println!();
+    println!();
 
-  if let Some(tags) = &tags.tags {
-    let mut tags: Vec<_> = tags.iter().collect();
-    tags.sort();
-    println!("{:width$} {:width2$?}", "Tags".green(), &tags, width = 15, width2 = 25);
-  }
-  if let Some(tags) = &tags.skip_tags {
-    let mut tags: Vec<_> = tags.iter().collect();
-    tags.sort();
-    println!("{:width$} {:width2$?}", "Skip-Tags".green(), &tags, width = 15, width2 = 25);
-  }
-  let items: Vec<_> = items.iter().filter(|item| !tags.hrw_should_skip_item(item)).collect();
+    if let Some(tags) = &tags.tags {
+        println!("{:width$} {}", "Tags".green(), tags, width = 15);
+    }
+    if let Some(tags) = &tags.skip_tags {
+        println!("{:width$} {}", "Skip-Tags".green(), tags, width = 15);
+    }
+    let items: Vec<_> = items.iter().filter(|item| !tags.hrw_should_skip_item(item)).collect();
 
-  if items.is_empty() {
-    println!("{}", "No items".red());
-    std::process::exit(1)
-  }
+    if items.is_empty() {
+        println!("{}", "No items".red());
+        std::process::exit(1)
+    }
 
-  for item in items {
-    let mut out_str = String::new();
-    let mut emitter = YamlEmitter::new(&mut out_str);
-    emitter.dump(item).unwrap();
-    println!("{out_str}");
-  }
+    for item in items {
+        let mut out_str = String::new();
+        let mut emitter = YamlEmitter::new(&mut out_str);
+        emitter.dump(item).unwrap();
+        println!("{out_str}");
+    }
 }
 
 pub fn hrw_list_benchmark_file_tags(benchmark_file: &str) {
     let docs = reader::hrw_read_file_as_yml(benchmark_file);
-    let items = reader::hrw_read_yaml_doc_accessor(&docs[0], Some("plan"));
+    let items = include::hrw_walk_items(benchmark_file, reader::hrw_read_yaml_doc_accessor(&docs[0], Some("plan")));
 
     println!();
 
@@ -103,7 +262,7 @@ pub fn hrw_list_benchmark_file_tags(benchmark_file: &str) {
     }
     let mut tags: HashSet<&str> = HashSet::new();
 
-    for item in items {
+    for item in items.iter() {
         if let Some(item_tags_raw) = item["tags"].as_vec() {
             tags.extend(item_tags_raw.iter().map(|t| t.as_str().unwrap()));
         }
@@ -128,12 +287,89 @@ mod tests {
     hrw_str_to_yaml("---\nname: foo\nrequest:\n  url: /\ntags:\n  - tag1\n  - tag2")
   }
 
+  // (--tags, --skip-tags, item tags, runs)
+  type HarrawTagCase<'a> = (Option<&'a str>, Option<&'a str>, &'a [&'a str], bool);
+
+  #[test]
+  fn hrw_tag_expression_matrix() {
+    let matrix: [HarrawTagCase; 26] = [
+      // Without a selection everything runs, except `never`
+      (None, None, &[], true),
+      (None, None, &["smoke"], true),
+      (None, None, &["never"], false),
+      // Plain names and comma lists
+      (Some("smoke"), None, &[], false),
+      (Some("smoke"), None, &["smoke", "slow"], true),
+      (Some("api,web"), None, &["web"], true),
+      (Some("api, web and slow"), None, &["web"], false),
+      // Boolean expressions, untagged items match negations
+      (Some("not slow"), None, &[], true),
+      (Some("smoke and not slow"), None, &["smoke"], true),
+      (Some("smoke and not slow"), None, &["smoke", "slow"], false),
+      (Some("(api or web) and !flaky"), None, &["web"], true),
+      (Some("(api or web) and !flaky"), None, &["api", "flaky"], false),
+      (Some("(api or web) and !flaky"), None, &["db"], false),
+      (Some("!(api or web)"), None, &["db"], true),
+      // `--skip-tags` wins over `--tags`
+      (Some("smoke"), Some("slow"), &["smoke", "slow"], false),
+      (None, Some("slow and not smoke"), &["slow", "smoke"], true),
+      (None, Some("slow and not smoke"), &["slow"], false),
+      // `always` runs whatever `--tags` says, unless skipped
+      (Some("smoke"), None, &["always"], true),
+      (Some("not always"), None, &["always"], true),
+      (None, Some("always"), &["always", "smoke"], false),
+      (None, Some("smoke"), &["always", "smoke"], false),
+      // `never` only runs when `--tags` names it and matches
+      (Some("smoke"), None, &["never", "smoke"], false),
+      (Some("not slow"), None, &["never"], false),
+      (Some("never"), None, &["never"], true),
+      (Some("never and smoke"), None, &["never", "smoke"], true),
+      (Some("never and smoke"), None, &["never"], false),
+    ];
+
+    for (tags_option, skip_tags_option, item_tags, runs) in matrix {
+      let tags = HarrawTags::new(tags_option, skip_tags_option);
+      let item = hrw_str_to_yaml(&format!("---\nname: foo\nrequest:\n  url: /\ntags: [{}]", item_tags.join(", ")));
+
+      assert_eq!(!tags.hrw_should_skip_item(&item), runs, "--tags {:?} --skip-tags {:?} on {:?}", tags_option, skip_tags_option, item_tags);
+    }
+  }
+
+  #[test]
+  fn hrw_tag_expression_syntax() {
+    let expression = HarrawTagExpression::hrw_parse("( api or web ) and !flaky,slow").unwrap();
+    assert_eq!(expression.to_string(), "(api or web) and not flaky or slow");
+    assert_eq!(HarrawTagExpression::hrw_parse("not (a and b)").unwrap().to_string(), "not (a and b)");
+
+    for invalid in ["", "smoke and", "(api", "api)", "and", "api web", "not"] {
+      assert!(HarrawTagExpression::hrw_parse(invalid).is_err(), "{invalid:?} should not parse");
+    }
+  }
+
+  #[test]
+  #[should_panic(expected = "Invalid `tags` expression `smoke and`")]
+  fn hrw_invalid_tag_expression() {
+    HarrawTags::new(Some("smoke and"), None);
+  }
+
   #[test]
   #[should_panic]
   fn hrw_same_tags_and_skip_tags() {
     let _ = HarrawTags::new(Some("tag1"), Some("tag1"));
   }
 
+  #[test]
+  fn hrw_expressions_may_name_skipped_tags() {
+    let tags = HarrawTags::new(Some("not slow"), Some("slow"));
+
+    assert!(tags.hrw_should_skip_item(&hrw_str_to_yaml("---\nname: foo\ntags:\n  - slow")));
+    assert!(!tags.hrw_should_skip_item(&hrw_str_to_yaml("---\nname: foo\ntags:\n  - api")));
+
+    let tags = HarrawTags::new(Some("api and not slow"), Some("slow,flaky"));
+
+    assert!(!tags.hrw_should_skip_item(&hrw_str_to_yaml("---\nname: foo\ntags:\n  - api")));
+  }
+
   #[test]
   fn hrw_empty_tags_both() {
     let item = hrw_str_to_yaml("---\nname: foo\nrequest:\n  url: /");